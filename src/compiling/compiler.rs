@@ -1,11 +1,12 @@
 use capitalize::Capitalize;
 use std::fs::File;
+use std::ops::Range;
 use std::io::prelude::*;
-use crate::compiling_rules::Rules;
+use crate::compiling_rules::{ Rules, RulesError, get_confusable };
 use crate::compiling::{Token, Lexer, LexerError, LexerErrorType, Metadata, SyntaxModule};
-use crate::compiling::failing::message::Message;
+use crate::compiling::failing::message::{ Message, MessageType };
 use crate::compiling::failing::failure::Failure;
-use crate::error_pos;
+use crate::compiling::failing::position_info::PositionInfo;
 
 
 /// How do you want to separate expressions?
@@ -18,10 +19,21 @@ pub enum SeparatorMode {
     /// Compiler uses ASI (automatic semicolon insertion) 
     /// and removes newlines that are not in the context of being a separator.
     /// This variant also requires a string that will represent the separator.
+    ///
+    /// Newlines inside of parentheses or brackets, after an operator or before
+    /// a line that begins with a binary operator are dropped. The rest are replaced with the separator.
+    /// Curly braces open a block in which newlines are significant again.
+    /// No separator is inserted before a closing curly brace or between a closing curly brace
+    /// and a keyword that continues the statement on the next line (such as `else`).
+    /// Binary operators can be changed with `Compiler::set_binary_operators`
+    /// and the keywords with `Compiler::set_continuation_keywords`.
     SemiAutomatic(String),
     /// Compiler instead of inserting semicolons assumes that all newlines end lines.
     /// However user can decide that he wants to continue expression on current line to the next one.
     /// This variant also requires a string that will represent the continuator.
+    ///
+    /// The continuator has to be the last thing on the line (trailing whitespace is allowed).
+    /// It joins the next line with the current one without emitting the newline or indentation.
     Automatic(String)
 }
 
@@ -34,10 +46,82 @@ pub enum SeparatorMode {
 pub enum ScopingMode {
     /// Scopes are going to be contained between two specified tokens
     Block,
-    /// Scopes are going to be determined by the indentation
+    /// Scopes are going to be determined by the indentation.
+    /// Lexer emits the indent and dedent tokens whenever the indentation level changes.
     Indent
 }
 
+/// How do you want to handle indentation that mixes tabs and spaces?
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum MixedIndentPolicy {
+    /// Mixing tabs and spaces is an error
+    #[default]
+    Reject,
+    /// Tabs and spaces can be mixed freely
+    Allow,
+    /// Mixing tabs and spaces is allowed, but lexer reports a warning
+    Warn
+}
+
+// Operators that cannot begin an expression and thus continue the statement from the previous line
+const BINARY_OPERATORS: [&str; 42] = [
+    ".", "?.", ",", "?", ":", "=", "=>", "==", "===", "!=", "!==",
+    "<", ">", "<=", ">=", "&&", "||", "??", "*", "/", "%", "**",
+    "&", "|", "^", "<<", ">>", ">>>", "+=", "-=", "*=", "/=", "%=",
+    "**=", "&=", "|=", "^=", "<<=", ">>=", "&&=", "||=", "??="
+];
+
+// Keywords that continue the statement after a closing curly brace on the previous line
+const CONTINUATION_KEYWORDS: [&str; 4] = ["else", "catch", "finally", "while"];
+
+/// Get the column that follows the letter at given column
+///
+/// Tab moves the column to the next tab stop and byte order mark does not take any space.
+/// Any other letter takes one column.
+pub fn get_next_column(col: usize, letter: char, tab_width: usize) -> usize {
+    match letter {
+        '\u{FEFF}' => col,
+        '\t' => {
+            let tab_width = tab_width.max(1);
+            (col - 1) / tab_width * tab_width + tab_width + 1
+        }
+        _ => col + 1
+    }
+}
+
+/// Get the amount of columns that the text takes when it begins at the beginning of the line
+pub fn get_text_width(text: &str, tab_width: usize) -> usize {
+    text.chars().fold(1, |col, letter| get_next_column(col, letter, tab_width)) - 1
+}
+
+/// Change of the source code (such as the one sent by an editor)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Byte range of the code that gets replaced
+    pub range: Range<usize>,
+    /// Text that replaces the range
+    pub text: String
+}
+
+impl TextEdit {
+    /// Create a new edit that replaces given byte range with the text
+    pub fn new<T: AsRef<str>>(range: Range<usize>, text: T) -> Self {
+        TextEdit {
+            range,
+            text: String::from(text.as_ref())
+        }
+    }
+}
+
+/// Beginning of the code that is skipped by the lexer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preamble {
+    /// Determines if the code begins with a byte order mark
+    pub bom: bool,
+    /// Shebang line at the beginning of the code (if shebangs are allowed by the rules)
+    pub shebang: Option<String>
+}
+
 /// Compiler that rules them all
 /// 
 /// Compiler is a central unit of heraclitus.
@@ -74,6 +158,17 @@ pub struct Compiler {
     pub separator_mode: SeparatorMode,
     /// Scoping mode for this compiler
     pub scoping_mode: ScopingMode,
+    /// Determines if tokens should carry their trivia (whitespace, newlines and trivia regions)
+    /// so that the source code can be reproduced from them
+    pub lossless: bool,
+    /// Amount of columns between tab stops (by default tab takes one column)
+    pub tab_width: usize,
+    /// Determines how the indentation that mixes tabs and spaces is handled
+    pub mixed_indent: MixedIndentPolicy,
+    /// Operators that continue the statement from the previous line in `SemiAutomatic` separator mode
+    pub binary_operators: Vec<String>,
+    /// Keywords that continue the statement after a closing curly brace in `SemiAutomatic` separator mode
+    pub continuation_keywords: Vec<String>,
     // Check if user wants to debug parser
     debug: bool
 }
@@ -88,15 +183,62 @@ impl Compiler {
             path: None,
             separator_mode: SeparatorMode::Manual,
             scoping_mode: ScopingMode::Block,
+            lossless: false,
+            tab_width: 1,
+            mixed_indent: MixedIndentPolicy::Reject,
+            binary_operators: BINARY_OPERATORS.iter().map(|operator| String::from(*operator)).collect(),
+            continuation_keywords: CONTINUATION_KEYWORDS.iter().map(|keyword| String::from(*keyword)).collect(),
             debug: false
         }
     }
 
+    /// Create a new compiler with provided rules after validating them
+    ///
+    /// Returns all the problems found in the rules instead of failing later while tokenizing.
+    /// Compiler created with `new` reports a dangling reference only once the lexer reaches it.
+    pub fn try_new<T: AsRef<str>>(name: T, rules: Rules) -> Result<Self, Vec<RulesError>> {
+        rules.validate()?;
+        Ok(Compiler::new(name, rules))
+    }
+
     /// Set the language to use indentations
     pub fn use_indents(&mut self) {
         self.scoping_mode = ScopingMode::Indent
     }
 
+    /// Set the amount of columns between tab stops
+    ///
+    /// It's used for columns of the tokens, widths of the indentation and snippets of the code in messages.
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        self.tab_width = tab_width
+    }
+
+    /// Set the operators that continue the statement from the previous line
+    ///
+    /// In `SemiAutomatic` separator mode a line that begins with one of these operators
+    /// is joined with the previous line. Any other line begins a new statement.
+    /// Unary operators such as `!`, `-` or `++` should not be listed here.
+    pub fn set_binary_operators<T: AsRef<str>>(&mut self, operators: Vec<T>) {
+        self.binary_operators = operators.iter().map(|operator| String::from(operator.as_ref())).collect();
+    }
+
+    /// Set the keywords that continue the statement after a closing curly brace
+    ///
+    /// In `SemiAutomatic` separator mode no separator is inserted between `}`
+    /// and a line that begins with one of these keywords, such as `} else {`.
+    pub fn set_continuation_keywords<T: AsRef<str>>(&mut self, keywords: Vec<T>) {
+        self.continuation_keywords = keywords.iter().map(|keyword| String::from(keyword.as_ref())).collect();
+    }
+
+    /// Set the lexer to preserve trivia of the tokens
+    ///
+    /// Each token stores the whitespace, newlines and trivia regions that surround it
+    /// in `leading_trivia` and `trailing_trivia`. Concatenating `Token::to_source`
+    /// of all the tokens reproduces the source code byte for byte.
+    pub fn preserve_trivia(&mut self) {
+        self.lossless = true
+    }
+
     /// Load file from path
     pub fn load_file(mut self, file_path: String) -> std::io::Result<()> {
         let mut file = File::open(&file_path)?;
@@ -119,9 +261,95 @@ impl Compiler {
 
     /// Run just lexer
     pub fn tokenize(&self) -> Result<Vec<Token>, LexerError> {
+        self.tokenize_with_preamble().map(|(lexem, _)| lexem)
+    }
+
+    /// Run just lexer and return the tokens together with the preamble that has been skipped
+    pub fn tokenize_with_preamble(&self) -> Result<(Vec<Token>, Preamble), LexerError> {
         let mut lexer = Lexer::new(self);
         lexer.run()?;
-        Ok(lexer.lexem)
+        let preamble = Preamble {
+            bom: lexer.bom,
+            shebang: lexer.shebang
+        };
+        Ok((lexer.lexem, preamble))
+    }
+
+    /// Apply the edit to the code and run lexer only on the part of the code that could have changed
+    ///
+    /// The tokens have to be the result of lexing the code before the edit.
+    /// Lexing restarts from the last safe point before the edit and the result
+    /// is the same as if the entire code was lexed again.
+    ///
+    /// The edit is kept only if the edited code has been lexed successfully.
+    /// Range that lies outside of the code or splits a character is reported as `InvalidEdit`.
+    pub fn tokenize_incremental(&mut self, tokens: &[Token], edit: TextEdit) -> Result<Vec<Token>, LexerError> {
+        let code = self.code.as_deref().unwrap_or_default();
+        let TextEdit { range, text } = edit;
+        let is_valid = range.start <= range.end && range.end <= code.len()
+            && code.is_char_boundary(range.start) && code.is_char_boundary(range.end);
+        if !is_valid {
+            return Err((
+                LexerErrorType::InvalidEdit,
+                PositionInfo::at_pos(self.path.clone(), (1, 1), 0).data(format!("byte range {}..{}", range.start, range.end))
+            ));
+        }
+        let mut edited = code.to_string();
+        edited.replace_range(range.clone(), &text);
+        let previous = self.code.replace(edited);
+        let mut lexer = Lexer::new(self);
+        lexer.resume(tokens, range.start);
+        let result = lexer.run().map(|_| lexer.lexem);
+        // Code stays in sync with the tokens that have been returned last time
+        if result.is_err() {
+            self.code = previous;
+        }
+        result
+    }
+
+    /// Run just lexer that recovers from errors
+    ///
+    /// Returns all the tokens together with all the errors that have been found
+    pub fn tokenize_recovering(&self) -> (Vec<Token>, Vec<LexerError>) {
+        let mut lexer = Lexer::new(self);
+        let errors = lexer.run_recovering();
+        (lexer.lexem, errors)
+    }
+
+    // Create a message describing the lexer error (or warning)
+    fn get_lexer_message<M: Metadata>(&self, meta: &M, (kind, info): LexerError, message_type: MessageType) -> Message {
+        let raw_data = info.data.clone().unwrap();
+        let data = raw_data.capitalize();
+        let message = match kind {
+            LexerErrorType::Singleline => format!("{data} cannot be multiline"),
+            LexerErrorType::Unclosed => format!("{data} unclosed"),
+            LexerErrorType::UnclosedNested(opened_at) => {
+                let levels = opened_at.iter()
+                    .map(|(row, col)| format!("{row}:{col}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{data} unclosed at nesting depth {} (levels opened at {levels})", opened_at.len())
+            }
+            LexerErrorType::DanglingContinuator => format!("{data} has no line to continue"),
+            LexerErrorType::InconsistentIndent => format!("{data} does not match any outer indentation level"),
+            LexerErrorType::MixedIndent => format!("{data} cannot mix tabs and spaces"),
+            LexerErrorType::InvalidEscape => format!("Invalid escape sequence '{raw_data}'"),
+            LexerErrorType::InputError => format!("Could not read the code: {raw_data}"),
+            LexerErrorType::InvalidCharacter(letter) => match get_confusable(letter) {
+                Some(hint) => format!("Invalid character '{}' ({raw_data}), did you mean '{hint}'?", letter.escape_debug()),
+                None => format!("Invalid character '{}' ({raw_data})", letter.escape_debug())
+            },
+            LexerErrorType::DanglingReference(reference) => format!("{data} references region '{reference}' that does not exist"),
+            LexerErrorType::InvalidEdit => format!("{data} does not fit the code"),
+        };
+        let message_at_position = match message_type {
+            MessageType::Warning => Message::new_warn_at_position,
+            MessageType::Info => Message::new_info_at_position,
+            MessageType::Error => Message::new_err_at_position
+        };
+        message_at_position(meta, info)
+            .message(message)
+            .comment("test")
     }
 
     /// Parser will display information about the call stack
@@ -130,31 +358,57 @@ impl Compiler {
     }
 
     /// Bulk run lexer and parser (used for testing purposes)
+    ///
+    /// All the lexer errors are reported at once. If there is more than
+    /// one of them, they are returned in the `Failure::Many` variant.
     pub fn compile<M: Metadata>(&self, module: &mut impl SyntaxModule<M>) -> Result<M, Failure> {
-        match self.tokenize() {
-            Ok(lexem) => {
-                let mut meta = M::new(lexem, self.path.clone(), self.code.clone());
-                if self.debug {
-                    module.parse_debug(&mut meta)?;
-                } else {
-                    module.parse(&mut meta)?;
-                }
-                Ok(meta)
-            }
-            Err((kind, info)) => {
-                let data = info.data.clone().unwrap().capitalize();
-                // Create an error message
-                let message = match kind {
-                    LexerErrorType::Singleline => format!("{data} cannot be multiline"),
-                    LexerErrorType::Unclosed => format!("{data} unclosed"),
-                };
-                // Send error
-                let meta = M::new(vec![], self.path.clone(), self.code.clone());
-                error_pos!(&meta, info => {
-                    message: message,
-                    comment: "test"
-                })
-            }
+        let mut lexer = Lexer::new(self);
+        let errors = lexer.run_recovering();
+        let mut meta = M::new(vec![], self.path.clone(), self.code.clone());
+        meta.set_tab_width(self.tab_width);
+        for warning in lexer.warnings.drain(..) {
+            self.get_lexer_message(&meta, warning, MessageType::Warning).show();
         }
+        if !errors.is_empty() {
+            let mut messages = errors.into_iter()
+                .map(|error| self.get_lexer_message(&meta, error, MessageType::Error))
+                .collect::<Vec<_>>();
+            return Err(match messages.len() {
+                1 => Failure::Loud(messages.remove(0)),
+                _ => Failure::Many(messages)
+            })
+        }
+        let mut meta = M::new(lexer.lexem, self.path.clone(), self.code.clone());
+        meta.set_tab_width(self.tab_width);
+        if self.debug {
+            module.parse_debug(&mut meta)?;
+        } else {
+            module.parse(&mut meta)?;
+        }
+        Ok(meta)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::reg;
+    use crate::compiling_rules::{ Region, Rules };
+    use crate::compiling::{ DefaultMetadata, LexerErrorType, Metadata };
+    use crate::compiling::failing::message::MessageType;
+    use crate::compiling::failing::position_info::PositionInfo;
+    use super::Compiler;
+
+    #[test]
+    fn invalid_character_messages() {
+        let cc = Compiler::new("TestScript", Rules::new(vec![], vec![], reg![]));
+        let meta = DefaultMetadata::new(vec![], None, Some(String::from("a b")));
+        let get_message = |letter: char| {
+            let info = PositionInfo::at_pos(None, (1, 2), 1).data(format!("U+{:04X}", letter as u32));
+            cc.get_lexer_message(&meta, (LexerErrorType::InvalidCharacter(letter), info), MessageType::Error).message
+        };
+        // Invisible characters are escaped whether there is a hint or not
+        assert_eq!(get_message('\u{00A0}'), Some(String::from("Invalid character '\\u{a0}' (U+00A0), did you mean ' '?")));
+        assert_eq!(get_message('\u{200B}'), Some(String::from("Invalid character '\\u{200b}' (U+200B)")));
+        assert_eq!(get_message('；'), Some(String::from("Invalid character '；' (U+FF1B), did you mean ';'?")));
     }
 }
@@ -14,12 +14,17 @@ use crate::compiling::failing::position_info::PositionInfo;
 /// 
 /// The Loud failure is used when the parser cannot continue. It contains detailed information
 /// about the error such as token position and length, but also a message, comment and a full traceback.
+///
+/// The Many failure is a loud failure that consists of multiple messages. It is returned for example
+/// when the lexer has recovered from several errors so that all of them can be reported at once.
 #[derive(Debug, Clone)]
 pub enum Failure {
     /// Failure that is not important
     Quiet(PositionInfo),
     /// Failure that is important
-    Loud(Message)
+    Loud(Message),
+    /// Multiple failures that are important
+    Many(Vec<Message>)
 }
 
 impl Failure {
@@ -30,22 +35,36 @@ impl Failure {
 
     /// Returns true if this failure is loud
     pub fn is_loud(&self) -> bool {
-        matches!(self, Failure::Loud(_))
+        matches!(self, Failure::Loud(_) | Failure::Many(_))
     }
 
     /// Unwraps this failure into quiet failure
     pub fn unwrap_quiet(self) -> PositionInfo {
         match self {
             Failure::Quiet(info) => info,
-            Failure::Loud(_) => panic!("Cannot quietly unwrap loud failure")
+            Failure::Loud(_) | Failure::Many(_) => panic!("Cannot quietly unwrap loud failure")
         }
     }
 
     /// Unwraps this failure into loud failure
+    ///
+    /// If this failure consists of many messages, the first one is returned
     pub fn unwrap_loud(self) -> Message {
         match self {
             Failure::Quiet(_) => panic!("Cannot loudly unwrap quiet failure"),
-            Failure::Loud(message) => message
+            Failure::Loud(message) => message,
+            Failure::Many(messages) => messages.into_iter().next().expect("Cannot loudly unwrap empty failure")
+        }
+    }
+
+    /// Returns all the messages of this failure
+    ///
+    /// Quiet failure does not contain any messages
+    pub fn into_messages(self) -> Vec<Message> {
+        match self {
+            Failure::Quiet(_) => vec![],
+            Failure::Loud(message) => vec![message],
+            Failure::Many(messages) => messages
         }
     }
 }
\ No newline at end of file
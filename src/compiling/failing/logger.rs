@@ -6,13 +6,15 @@ use pad::PadStr;
 use crate::compiling::failing::position_info::PositionInfo;
 use crate::compiling::failing::message::MessageType;
 use crate::prelude::Position;
+use crate::compiling::get_next_column;
 
 /// This is a logger that is used to log messages to the user
 /// The logger is being used internally by the Message struct
 /// when invoking the `show` method
 pub struct Logger {
     kind: MessageType,
-    trace: Vec<PositionInfo>
+    trace: Vec<PositionInfo>,
+    tab_width: usize
 }
 
 impl Logger {
@@ -20,10 +22,32 @@ impl Logger {
     pub fn new(kind: MessageType, trace: &[PositionInfo]) -> Self {
         Logger {
             kind,
-            trace: trace.to_vec()
+            trace: trace.to_vec(),
+            tab_width: 1
         }
     }
 
+    /// Set amount of columns between tab stops
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
+
+    // Replaces tabs with spaces so that columns match the ones reported by the lexer
+    fn expand_tabs(&self, line: &str) -> String {
+        let mut col = 1;
+        let mut result = String::new();
+        for letter in line.chars() {
+            let next = get_next_column(col, letter, self.tab_width);
+            match letter {
+                '\t' => result.push_str(&" ".repeat(next - col)),
+                _ => result.push(letter)
+            }
+            col = next;
+        }
+        result
+    }
+
     fn kind_to_color(&self) -> Color {
         match self.kind {
             MessageType::Error => Color::Red,
@@ -140,7 +164,7 @@ impl Logger {
     }
 
     // Return requested row with appropriate coloring
-    fn get_snippet_row(&self, code: &Vec<String>, index: usize, offset: i8, overflow: &mut usize) -> Option<String> {
+    fn get_snippet_row(&self, code: &[String], index: usize, offset: i8, overflow: &mut usize) -> Option<String> {
         let (row, col, len) = self.get_row_col_len()?;
         let max_pad = self.get_max_pad_size(code.len())?;
         let index = index as i32 + offset as i32;
@@ -156,7 +180,7 @@ impl Logger {
             if end - 1 > code.chars().count() {
                 // We substract here 2 because 1 is the offset of col (starts at 1)
                 // and other 1 is the new line character that we do not display
-                *overflow = (end - 2).checked_sub(code.chars().count()).unwrap_or(0);
+                *overflow = (end - 2).saturating_sub(code.chars().count());
             }
             Some(format!("{line}| {formatted}"))
         }
@@ -202,7 +226,7 @@ impl Logger {
         let mut overflow = 0;
         let index = row - 1;
         let code = code.split('\n')
-            .map(|item| item.trim_end().to_string())
+            .map(|item| self.expand_tabs(item.trim_end()))
             .collect::<Vec<String>>();
         eprintln!();
         // Show additional code above the snippet
@@ -271,8 +295,8 @@ mod test {
         ].join("\n");
         // Uncomment to see the error message
         sleep(Duration::from_secs(1));
-        let begin = Token { word: "12".to_string(), pos: (1, 5), start: 4 };
-        let end = Token { word: ")".to_string(), pos: (1, 12), start: 11 };
+        let begin = Token { word: "12".to_string(), pos: (1, 5), start: 4, ..Default::default() };
+        let end = Token { word: ")".to_string(), pos: (1, 12), start: 11, ..Default::default() };
         let mut meta = DefaultMetadata::new(vec![], Some("/path/to/foo".to_string()), Some(code.clone()));
         let trace = [
             PositionInfo::from_between_tokens(&mut meta, Some(begin), Some(end))
@@ -283,4 +307,10 @@ mod test {
             .path()
             .snippet(Some(code));
     }
+
+    #[test]
+    fn test_tab_expansion() {
+        let logger = super::Logger::new(MessageType::Error, &[]).tab_width(4);
+        assert_eq!(logger.expand_tabs("\tab\tc  \td"), "    ab  c   d");
+    }
 }
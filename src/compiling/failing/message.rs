@@ -48,7 +48,9 @@ pub struct Message {
     /// Optionally store message
     pub message: Option<String>,
     /// Optionally store comment
-    pub comment: Option<String>
+    pub comment: Option<String>,
+    /// Amount of columns between tab stops in the code
+    pub tab_width: usize
 }
 
 impl Message {
@@ -59,7 +61,8 @@ impl Message {
             trace: trace.iter().rev().cloned().collect(),
             code: code.cloned(),
             message: None,
-            comment: None
+            comment: None,
+            tab_width: 1
         }
     }
 
@@ -70,13 +73,15 @@ impl Message {
             trace: vec![],
             code: None,
             message: Some(message.as_ref().to_string()),
-            comment: None
+            comment: None,
+            tab_width: 1
         }
     }
 
     /// Create a new error instance at given token position if possible
     pub fn new_at_token(meta: &impl Metadata, token: Option<Token>, kind: MessageType) -> Self {
         Self::new(meta.get_code(), &Self::get_full_trace(meta, PositionInfo::from_token(meta, token)), kind)
+            .tab_width(meta.get_tab_width())
     }
 
     /* New Error Message */
@@ -118,16 +123,19 @@ impl Message {
     /// Create an error by supplying essential information about the location
     pub fn new_err_at_position(meta: &impl Metadata, pos: PositionInfo) -> Self {
         Self::new(meta.get_code(), &Self::get_full_trace(meta, pos), MessageType::Error)
+            .tab_width(meta.get_tab_width())
     }
 
     /// Create a warning by supplying essential information about the location
     pub fn new_warn_at_position(meta: &impl Metadata, pos: PositionInfo) -> Self {
         Self::new(meta.get_code(), &Self::get_full_trace(meta, pos), MessageType::Warning)
+            .tab_width(meta.get_tab_width())
     }
 
     /// Create an info by supplying essential information about the location
     pub fn new_info_at_position(meta: &impl Metadata, pos: PositionInfo) -> Self {
         Self::new(meta.get_code(), &Self::get_full_trace(meta, pos), MessageType::Info)
+            .tab_width(meta.get_tab_width())
     }
 
     /* Attach additional infromation */
@@ -144,12 +152,19 @@ impl Message {
         self
     }
 
+    /// Set amount of columns between tab stops in the code
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
+
     /// Shows (renders) the message while giving
     /// the ownership to this object away
     pub fn show(&self) {
         // If this error is based in code
         if !self.trace.is_empty() {
             Logger::new(self.kind.clone(), &self.trace)
+                .tab_width(self.tab_width)
                 .header(self.kind.clone())
                 .line(self.message.clone())
                 .path()
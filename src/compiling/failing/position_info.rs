@@ -5,7 +5,8 @@
 
 use std::fs::File;
 use std::io::*;
-use crate::compiling::{Metadata, Token};
+use std::ops::Range;
+use crate::compiling::{Metadata, Token, Span};
 
 /// Store position of some error
 #[derive(Debug, Clone)]
@@ -26,7 +27,10 @@ pub struct PositionInfo {
     /// Length of the token
     pub len: usize,
     /// Additional information
-    pub data: Option<String>
+    pub data: Option<String>,
+    /// Location in the code with byte offsets if it is known
+    /// (boxed so that errors stay small)
+    pub span: Option<Box<Span>>
 }
 
 impl PositionInfo {
@@ -36,7 +40,8 @@ impl PositionInfo {
             position,
             path: meta.get_path(),
             len,
-            data: None
+            data: None,
+            span: None
         }.updated_pos(meta)
     }
 
@@ -46,7 +51,8 @@ impl PositionInfo {
             path: meta.get_path(),
             position: Position::EOF,
             len: 0,
-            data: None
+            data: None,
+            span: None
         }.updated_pos(meta)
     }
 
@@ -56,10 +62,36 @@ impl PositionInfo {
             path,
             position: Position::Pos(row, col),
             len,
-            data: None
+            data: None,
+            span: None
         }
     }
 
+    /// Create a new error at given span
+    ///
+    /// The length is measured in characters if the span does not exceed one line
+    pub fn from_span(path: Option<String>, span: Span) -> Self {
+        let (row, col) = span.start_pos;
+        let len = if span.end_pos.0 == row { span.end_pos.1 - col } else { 0 };
+        PositionInfo {
+            span: Some(Box::new(span)),
+            ..PositionInfo::at_pos(path, (row, col), len)
+        }
+    }
+
+    /// Get the range of bytes in the code that this position covers
+    pub fn get_byte_range(&self) -> Option<Range<usize>> {
+        self.span.as_ref().map(|span| span.start..span.end)
+    }
+
+    /// Get the position where row is accompanied by a column counted in UTF-16 code units
+    ///
+    /// This is the format that is used by the Language Server Protocol (columns start from 1 here though)
+    pub fn get_utf16_pos(&self) -> Option<(usize, usize)> {
+        let span = self.span.as_ref()?;
+        Some((span.start_pos.0, span.utf16_col?))
+    }
+
     fn updated_pos(mut self, meta: &impl Metadata) -> Self {
         let (row, col) = self.get_pos_by_file_or_code(meta.get_code());
         self.position = Position::Pos(row, col);
@@ -87,7 +119,10 @@ impl PositionInfo {
     /// and error once you finished parsing the entire expression
     pub fn from_token(meta: &impl Metadata, token_opt: Option<Token>) -> Self {
         match token_opt {
-            Some(token) => PositionInfo::at_pos(meta.get_path(), token.pos, token.word.chars().count()),
+            Some(token) => PositionInfo {
+                span: Some(Box::new(token.span)),
+                ..PositionInfo::at_pos(meta.get_path(), token.pos, token.word.chars().count())
+            },
             None => PositionInfo::at_eof(meta)
         }
     }
@@ -99,9 +134,13 @@ impl PositionInfo {
     pub fn from_between_tokens(meta: &impl Metadata, begin: Option<Token>, end: Option<Token>) -> Self {
         if let Some(begin) = begin {
             let (row, col) = begin.pos;
-            let end = end.map_or(usize::max_value(), |tok| tok.start);
+            let span = end.as_ref().map(|tok| Box::new(begin.span.until(&tok.span)));
+            let end = end.map_or(usize::MAX, |tok| tok.start);
             let len = end - begin.start;
-            PositionInfo::at_pos(meta.get_path(), (row, col), len)
+            PositionInfo {
+                span,
+                ..PositionInfo::at_pos(meta.get_path(), (row, col), len)
+            }
         }
         else {
             PositionInfo::from_metadata(meta)
@@ -175,11 +214,27 @@ mod test {
 
     #[test]
     fn test_position_info_between_tokens() {
-        let begin = Token { word: "begin".to_string(), pos: (1, 1), start: 0 };
-        let to = Token { word: "to".to_string(), pos: (1, 7), start: 6 };
-        let end = Token { word: "end".to_string(), pos: (1, 10), start: 9 };
+        let begin = Token { word: "begin".to_string(), pos: (1, 1), start: 0, ..Default::default() };
+        let to = Token { word: "to".to_string(), pos: (1, 7), start: 6, ..Default::default() };
+        let end = Token { word: "end".to_string(), pos: (1, 10), start: 9, ..Default::default() };
         let mut meta = DefaultMetadata::new(vec![begin.clone(), to.clone(), end.clone()], None, Some("begin to end".to_string()));
         let pos = PositionInfo::from_between_tokens(&mut meta, Some(begin.clone()), Some(end.clone()));
         assert_eq!(pos.len, end.start - begin.start);
     }
+
+    #[test]
+    fn test_position_info_span() {
+        let span = Span { start: 4, end: 8, start_pos: (1, 5), end_pos: (1, 6), utf16_col: Some(5) };
+        let clef = Token { word: "𝄞".to_string(), pos: (1, 5), start: 4, span, ..Default::default() };
+        let eq = Span { start: 9, end: 10, start_pos: (1, 7), end_pos: (1, 8), utf16_col: Some(8) };
+        let equals = Token { word: "=".to_string(), pos: (1, 7), start: 6, span: eq, ..Default::default() };
+        let meta = DefaultMetadata::new(vec![clef.clone(), equals.clone()], None, Some("let 𝄞 = 1".to_string()));
+        let pos = PositionInfo::from_token(&meta, Some(clef.clone()));
+        assert_eq!(pos.get_byte_range(), Some(4..8));
+        assert_eq!(pos.get_utf16_pos(), Some((1, 5)));
+        let pos = PositionInfo::from_between_tokens(&meta, Some(clef), Some(equals));
+        assert_eq!(pos.get_byte_range(), Some(4..9));
+        let pos = PositionInfo::from_span(None, eq);
+        assert_eq!((pos.len, pos.get_pos_by_code("")), (1, (1, 7)));
+    }
 }
@@ -10,75 +10,126 @@ pub enum CompoundReaction {
     Pass
 }
 
+// Trie of compounds where each node
+// determines if some compound ends here
+#[derive(Default)]
+struct CompoundTree {
+    children: HashMap<char, CompoundTree>,
+    is_compound: bool
+}
+
+impl CompoundTree {
+    fn insert(&mut self, compound: &str) {
+        let node = compound.chars().fold(self, |node, letter| {
+            node.children.entry(letter).or_default()
+        });
+        node.is_compound = true;
+    }
+}
+
 pub struct CompoundHandler {
-    compound_tree: HashMap<char, Vec<char>>,
-    is_triggered: bool
+    compound_pairs: HashMap<char, Vec<char>>,
+    compound_tree: CompoundTree,
+    // Amount of letters left in the compound that is being matched
+    remaining: usize
 }
 
 // Handles compounds
 impl CompoundHandler {
     pub fn new(rules: &Rules) -> Self {
         CompoundHandler {
-            compound_tree: Self::generate_compunds(rules.compounds.clone()),
-            is_triggered: false
+            compound_pairs: Self::generate_pairs(&rules.compounds),
+            compound_tree: Self::generate_compounds(&rules.long_compounds),
+            remaining: 0
         }
     }
 
-    // Generates a tree where the key is the left item of 
+    // Generates a tree where the key is the left item of
     // the pair and values are all the right items of the pair
-    fn generate_compunds(word_pairs: Vec<(char, char)>) -> HashMap<char, Vec<char>> {
-        let mut compound_tree = HashMap::new();
-        for (left, right) in word_pairs {
-            compound_tree
-                .entry(left)
-                .or_insert(vec![])
-                .push(right);
+    fn generate_pairs(word_pairs: &[(char, char)]) -> HashMap<char, Vec<char>> {
+        let mut compound_pairs: HashMap<char, Vec<char>> = HashMap::new();
+        for (left, right) in word_pairs.iter() {
+            compound_pairs
+                .entry(*left)
+                .or_default()
+                .push(*right);
+        }
+        compound_pairs
+    }
+
+    // Generates a trie of all the compounds
+    // that is used to find the longest match
+    fn generate_compounds(compounds: &[String]) -> CompoundTree {
+        let mut compound_tree = CompoundTree::default();
+        for compound in compounds.iter() {
+            // Compound has to consist of at least two letters
+            if compound.chars().count() > 1 {
+                compound_tree.insert(compound);
+            }
         }
         compound_tree
     }
 
+    // Finds length of the chain of overlapping pairs that begins with current letter
+    fn match_pairs(&self, reader: &Reader) -> usize {
+        let mut size = 1;
+        while let Some(future) = reader.get_future(size + 1) {
+            let mut letters = future.chars().skip(size - 1);
+            let (Some(left), Some(right)) = (letters.next(), letters.next()) else {
+                break
+            };
+            match self.compound_pairs.get(&left) {
+                Some(entries) if entries.contains(&right) => size += 1,
+                _ => break
+            }
+        }
+        if size > 1 { size } else { 0 }
+    }
+
+    // Finds length of the longest compound that begins with current letter
+    fn match_longest(&self, reader: &Reader) -> usize {
+        let mut node = &self.compound_tree;
+        let mut longest = 0;
+        let mut size = 1;
+        while let Some(future) = reader.get_future(size) {
+            match future.chars().last().and_then(|letter| node.children.get(&letter)) {
+                Some(child) => node = child,
+                None => break
+            }
+            if node.is_compound {
+                longest = size;
+            }
+            size += 1;
+        }
+        longest
+    }
+
     // Determines what shall we do with detected compound
-    pub fn handle_compound(&mut self, letter: char, reader: &Reader, is_tokenize: bool) -> CompoundReaction {
+    pub fn handle_compound(&mut self, _letter: char, reader: &Reader, is_tokenize: bool) -> CompoundReaction {
         // If the region is not tokenizable, we do not need to check for compounds
         if !is_tokenize {
-            self.is_triggered = false;
+            self.remaining = 0;
             return CompoundReaction::Pass;
         }
-        // Get completing symbol for current symbol
-        if let Some(entries) = self.compound_tree.get(&letter) {
-            // For any of the completing symbols
-            // check if future symbol satisfies at least one
-            for entry in entries.iter() {
-                // Get future string of current letter and the next one
-                if let Some(future) = reader.get_future(2) {
-                    let future_letter = future.chars().nth(1).unwrap();
-                    // Check if next character matches our desired symbol
-                    if future_letter == *entry {
-                        // If we matched before as well then this means
-                        // that this is a chain of compounds
-                        return if self.is_triggered {
-                            CompoundReaction::Keep
-                        }
-                        // If it's the first time, then this means
-                        // that this is a beginning
-                        else {
-                            self.is_triggered = true;
-                            CompoundReaction::Begin
-                        }
-                    }
-                }
+        // Continue the compound that is currently being matched
+        match self.remaining {
+            0 => {},
+            1 => {
+                self.remaining = 0;
+                return CompoundReaction::End
+            }
+            _ => {
+                self.remaining -= 1;
+                return CompoundReaction::Keep
             }
         }
-        // If we matched before and no match
-        // was found - end the compound
-        if self.is_triggered {
-            self.is_triggered = false;
-            CompoundReaction::End
-        }
-        // If nothing happened and we didn't
-        // match before - carry on with lexing
-        else {
-            CompoundReaction::Pass
+        // Try to begin a new compound
+        match self.match_pairs(reader).max(self.match_longest(reader)) {
+            0 => CompoundReaction::Pass,
+            size => {
+                self.remaining = size - 1;
+                CompoundReaction::Begin
+            }
         }
     }
 }
@@ -115,4 +166,60 @@ mod test {
         }
         assert_eq!(expected, result);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn match_longest_compound() {
+        let expected = vec![
+            CompoundReaction::Begin,
+            CompoundReaction::Keep,
+            CompoundReaction::End,
+            CompoundReaction::Pass,
+            CompoundReaction::Pass,
+            CompoundReaction::Begin,
+            CompoundReaction::Keep,
+            CompoundReaction::End,
+            CompoundReaction::Begin,
+            CompoundReaction::Keep,
+            CompoundReaction::End
+        ];
+        let code = "===!=<=>!==".to_string();
+        let mut reader = Reader::new(&code);
+        let symbols = vec!['<', '=', '>', '!'];
+        let rules = Rules::new(symbols, vec![('=', '=')], reg![])
+            .add_compounds(vec!["===", "!==", "<=>"]);
+        let mut ch = CompoundHandler::new(&rules);
+        let mut result = vec![];
+        // Simulate matching compounds
+        while let Some(letter) = reader.next() {
+            result.push(ch.handle_compound(letter, &reader, true));
+        }
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn match_chained_pairs() {
+        let expected = vec![
+            CompoundReaction::Pass,
+            CompoundReaction::Begin,
+            CompoundReaction::Keep,
+            CompoundReaction::End,
+            CompoundReaction::Pass,
+            CompoundReaction::Begin,
+            CompoundReaction::Keep,
+            CompoundReaction::End,
+            CompoundReaction::Pass
+        ];
+        let code = "a+++b<==c".to_string();
+        let mut reader = Reader::new(&code);
+        let symbols = vec!['+', '<', '='];
+        let compounds = vec![('+', '+'), ('<', '='), ('=', '=')];
+        let rules = Rules::new(symbols, compounds, reg![]);
+        let mut ch = CompoundHandler::new(&rules);
+        let mut result = vec![];
+        // Simulate matching compounds
+        while let Some(letter) = reader.next() {
+            result.push(ch.handle_compound(letter, &reader, true));
+        }
+        assert_eq!(expected, result);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::compiling::{ Token, TokenKind, MixedIndentPolicy, get_text_width };
+use super::LexerErrorType;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum IndentReaction {
+    Indent,
+    Dedent(usize),
+    Keep
+}
+
+pub struct IndentHandler {
+    indent_stack: Vec<usize>,
+    indent_char: Option<char>,
+    tab_width: usize,
+    mixed_indent: MixedIndentPolicy
+}
+
+// Handles indentation levels
+impl IndentHandler {
+    pub fn new(tab_width: usize, mixed_indent: MixedIndentPolicy) -> Self {
+        IndentHandler {
+            indent_stack: vec![0],
+            indent_char: None,
+            tab_width,
+            mixed_indent
+        }
+    }
+
+    // Checks if indentation mixes tabs and spaces (with itself or the previous indentation)
+    pub fn is_mixed(&mut self, indent: &str) -> bool {
+        self.check_whitespace(indent).is_err()
+    }
+
+    // Checks if indentation consists of one kind of whitespace
+    fn check_whitespace(&mut self, indent: &str) -> Result<(), LexerErrorType> {
+        for letter in indent.chars() {
+            match self.indent_char {
+                Some(indent_char) if indent_char != letter => return Err(LexerErrorType::MixedIndent),
+                Some(_) => {},
+                None => self.indent_char = Some(letter)
+            }
+        }
+        Ok(())
+    }
+
+    // Determines if the new line opens or closes some indentation levels
+    pub fn handle_indent(&mut self, indent: &str) -> Result<IndentReaction, LexerErrorType> {
+        if self.mixed_indent == MixedIndentPolicy::Reject {
+            self.check_whitespace(indent)?;
+        }
+        let size = get_text_width(indent, self.tab_width);
+        let last = *self.indent_stack.last().unwrap();
+        if size > last {
+            self.indent_stack.push(size);
+            return Ok(IndentReaction::Indent)
+        }
+        let mut dedents = 0;
+        while size < *self.indent_stack.last().unwrap() {
+            self.indent_stack.pop();
+            dedents += 1;
+        }
+        // Dedent has to land on one of the previous indentation levels
+        if size != *self.indent_stack.last().unwrap() {
+            return Err(LexerErrorType::InconsistentIndent)
+        }
+        Ok(match dedents {
+            0 => IndentReaction::Keep,
+            dedents => IndentReaction::Dedent(dedents)
+        })
+    }
+
+    // Restores the indentation levels from the tokens that have been lexed
+    // (the first indentation always opens a level, so it determines the indentation character)
+    pub fn resume(&mut self, lexem: &[Token], code: &str) {
+        for token in lexem.iter() {
+            match token.kind {
+                TokenKind::Indent => {
+                    if self.indent_char.is_none() {
+                        let line = &code[..token.span.start];
+                        let begin = line.rfind('\n').map_or(0, |index| index + 1);
+                        self.indent_char = line[begin..].chars().next();
+                    }
+                    self.indent_stack.push(token.pos.1 - 1);
+                }
+                TokenKind::Dedent => { self.indent_stack.pop(); }
+                _ => {}
+            }
+        }
+    }
+
+    // Closes all indentation levels that are left open
+    pub fn finish(&mut self) -> usize {
+        let dedents = self.indent_stack.len() - 1;
+        self.indent_stack.truncate(1);
+        dedents
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ IndentHandler, IndentReaction };
+    use crate::compiling::{ LexerErrorType, MixedIndentPolicy };
+
+    #[test]
+    fn match_indent() {
+        let expected = vec![
+            IndentReaction::Indent,
+            IndentReaction::Keep,
+            IndentReaction::Indent,
+            IndentReaction::Dedent(2),
+            IndentReaction::Indent
+        ];
+        let mut ih = IndentHandler::new(1, MixedIndentPolicy::Reject);
+        let mut result = vec![];
+        for indent in ["  ", "  ", "    ", "", "  "] {
+            result.push(ih.handle_indent(indent).unwrap());
+        }
+        assert_eq!(expected, result);
+        assert_eq!(ih.finish(), 1);
+    }
+
+    #[test]
+    fn invalid_indent() {
+        let mut ih = IndentHandler::new(1, MixedIndentPolicy::Reject);
+        assert!(ih.handle_indent("    ").is_ok());
+        assert!(matches!(ih.handle_indent("  "), Err(LexerErrorType::InconsistentIndent)));
+        let mut ih = IndentHandler::new(1, MixedIndentPolicy::Reject);
+        assert!(ih.handle_indent("  ").is_ok());
+        assert!(matches!(ih.handle_indent("\t"), Err(LexerErrorType::MixedIndent)));
+    }
+}
@@ -1,8 +1,15 @@
-use crate::compiling::{ Compiler, Token, SeparatorMode, ScopingMode };
+use std::collections::{ HashSet, VecDeque };
+use std::io::{ BufRead, BufReader, Read };
+use crate::compiling::{ Compiler, Token, TokenKind, Span, SeparatorMode, ScopingMode, MixedIndentPolicy };
+use crate::compiling::{ get_next_column, get_text_width };
 use super::compound_handler::{CompoundHandler, CompoundReaction};
 use super::region_handler::{ RegionHandler, RegionReaction };
+use super::separator_handler::SeparatorHandler;
+use super::span_handler::SpanHandler;
+use super::indent_handler::{ IndentHandler, IndentReaction };
 use super::reader::Reader;
 use crate::compiling::failing::position_info::PositionInfo;
+use crate::compiling_rules::{ TokenMatcher, EscapePolicy, Delimiter, CharClass };
 
 // This is just an estimation of token amount
 // inside of a typical 200-lined file.
@@ -14,12 +21,71 @@ pub enum LexerErrorType {
     /// Unspillable region has been spilled
     Singleline,
     /// Given region left unclosed
-    Unclosed
+    Unclosed,
+    /// Nestable region left unclosed with positions (row, column)
+    /// where each of the nesting levels has been opened
+    UnclosedNested(Vec<(usize, usize)>),
+    /// Line continuator has no line to continue to
+    DanglingContinuator,
+    /// Dedent does not match any outer indentation level
+    InconsistentIndent,
+    /// Indentation mixes tabs and spaces
+    MixedIndent,
+    /// Escape sequence in a region is not valid
+    InvalidEscape,
+    /// Code could not be read from the input
+    InputError,
+    /// Character outside of regions does not belong to any of the allowed classes
+    InvalidCharacter(char),
+    /// Region references a region that does not exist (with the id of the reference)
+    DanglingReference(String),
+    /// Byte range of the text edit lies outside of the code or splits a character
+    InvalidEdit
 }
 
 /// Type containing full error of lexer
 pub type LexerError = (LexerErrorType, PositionInfo);
 
+/// Get position (row, column) of the letter at given byte offset of the token's word
+fn get_position_in_token(token: &Token, offset: usize, tab_width: usize) -> (usize, usize) {
+    token.word[..offset].chars().fold(token.span.start_pos, |(row, col), letter| {
+        if letter == '\n' { (row + 1, 1) } else { (row, get_next_column(col, letter, tab_width)) }
+    })
+}
+
+/// Replace doubled closing delimiters with single ones
+///
+/// The opening delimiter is not taken into account unless the region is continued after an interpolation.
+fn collapse_doubled(text: &str, begin: &str, end: &str, is_continued: bool) -> String {
+    let (prefix, body) = match text.strip_prefix(begin) {
+        Some(body) if !is_continued => (begin, body),
+        _ => ("", text)
+    };
+    format!("{prefix}{}", body.replace(&end.repeat(2), end))
+}
+
+/// Strip common indentation of the lines that follow the first line
+///
+/// Lines that consist only of whitespace do not count towards the common indentation.
+fn strip_common_indent(text: &str) -> String {
+    let mut lines = text.split('\n');
+    let first = lines.next().unwrap_or_default();
+    let rest = lines.collect::<Vec<_>>();
+    let indent_size = |line: &&str| line.chars().take_while(|letter| [' ', '\t'].contains(letter)).count();
+    let common = rest.iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(indent_size)
+        .min()
+        .unwrap_or(0);
+    let mut result = String::from(first);
+    for line in rest {
+        let size = indent_size(&line).min(common);
+        result.push('\n');
+        result.push_str(&line[size..]);
+    }
+    result
+}
+
 /// The Lexer
 ///
 /// Lexer takes source code in a form of a string and translates it to a list of tokens.
@@ -28,68 +94,411 @@ pub type LexerError = (LexerErrorType, PositionInfo);
 /// pass a reference to the `Compiler`.
 pub struct Lexer<'a> {
     symbols: Vec<char>,
+    keywords: HashSet<String>,
+    matchers: Vec<TokenMatcher>,
+    allowed_chars: Vec<CharClass>,
     escape_symbol: char,
     compound: CompoundHandler,
     region: RegionHandler,
+    separator: SeparatorHandler,
+    indent: IndentHandler,
+    span: SpanHandler,
     reader: Reader<'a>,
     path: Option<String>,
     /// This attribute stores parsed tokens by the lexer
     pub lexem: Vec<Token>,
     separator_mode: SeparatorMode,
     scoping_mode: ScopingMode,
+    lossless: bool,
     is_escaped: bool,
+    is_indenting: bool,
+    // Indentation of the line that begins with trivia
+    // (it's measured once the line turns out to hold some code)
+    pending_indent: Option<(String, usize, usize)>,
+    word: String,
     position: (usize, usize),
     index: usize,
-    token_start_index: usize
+    token_start_index: usize,
+    kind: TokenKind,
+    is_recovering: bool,
+    is_finished: bool,
+    errors: Vec<LexerError>,
+    /// Warnings that have been found while lexing (such as indentation that mixes tabs and spaces)
+    pub warnings: Vec<LexerError>,
+    /// Determines if the code begins with a byte order mark (which is skipped)
+    pub bom: bool,
+    /// Shebang line that has been skipped at the beginning of the code
+    pub shebang: Option<String>,
+    allow_shebang: bool,
+    mixed_indent: MixedIndentPolicy,
+    // Tokens (and the error that stopped lexing) that are ready to be taken by the iterator
+    queue: VecDeque<Result<Token, LexerError>>,
+    // Byte offset where the code that has not been attached as trivia begins
+    trivia_cursor: usize,
+    // Index of the first token that has not been processed for trivia
+    trivia_index: usize,
+    // Index of the last token that appears in the code
+    trivia_previous: Option<usize>
 }
 
 impl<'a> Lexer<'a> {
     /// Create a new Lexer based on the compiler metadata
     pub fn new(cc: &'a Compiler) -> Self {
         let code: &'a String = cc.code.as_ref().unwrap();
+        Self::with_reader(cc, Reader::new(code))
+    }
+
+    /// Create a new Lexer that reads the code from the input as it goes
+    ///
+    /// Only the part of the code that is being lexed is kept in memory.
+    /// Combined with the `Iterator` implementation this allows to tokenize large inputs in bounded memory.
+    pub fn from_reader(cc: &'a Compiler, input: impl BufRead + 'static) -> Self {
+        Self::with_reader(cc, Reader::from_input(input))
+    }
+
+    /// Create a new Lexer that reads the code from the buffered input
+    pub fn from_read(cc: &'a Compiler, input: impl Read + 'static) -> Self {
+        Self::from_reader(cc, BufReader::new(input))
+    }
+
+    fn with_reader(cc: &'a Compiler, mut reader: Reader<'a>) -> Self {
+        reader.tab_width = cc.tab_width;
         Lexer {
             symbols: cc.rules.symbols.clone(),
+            keywords: cc.rules.keywords.clone(),
+            matchers: cc.rules.matchers.clone(),
+            allowed_chars: cc.rules.allowed_chars.clone(),
             escape_symbol: cc.rules.escape_symbol,
             compound: CompoundHandler::new(&cc.rules),
             region: RegionHandler::new(&cc.rules),
-            reader: Reader::new(code),
+            separator: SeparatorHandler::new(cc),
+            indent: IndentHandler::new(cc.tab_width, cc.mixed_indent.clone()),
+            span: SpanHandler::new(),
+            reader,
             path: cc.path.clone(),
             lexem: Vec::with_capacity(AVG_TOKEN_AMOUNT),
             separator_mode: cc.separator_mode.clone(),
             scoping_mode: cc.scoping_mode.clone(),
+            lossless: cc.lossless,
             is_escaped: false,
+            is_indenting: false,
+            pending_indent: None,
+            word: String::new(),
             position: (0, 0),
             index: 0,
-            token_start_index: 0
+            token_start_index: 0,
+            kind: TokenKind::Word,
+            is_recovering: false,
+            is_finished: false,
+            errors: vec![],
+            warnings: vec![],
+            bom: false,
+            shebang: None,
+            allow_shebang: cc.rules.shebang,
+            mixed_indent: cc.mixed_indent.clone(),
+            queue: VecDeque::new(),
+            trivia_cursor: 0,
+            trivia_index: 0,
+            trivia_previous: None
+        }
+    }
+
+    /// Report an error
+    ///
+    /// When recovering the error is stored and lexing continues.
+    /// Otherwise the error is returned and lexing stops.
+    fn report(&mut self, error: LexerError) -> Result<(), LexerError> {
+        if self.is_recovering {
+            self.errors.push(error);
+            Ok(())
+        }
+        else { Err(error) }
+    }
+
+    /// Stop lexing if an error has been reported while pushing tokens
+    fn check_errors(&mut self) -> Result<(), LexerError> {
+        if !self.is_recovering && !self.errors.is_empty() {
+            return Err(self.errors.remove(0))
+        }
+        Ok(())
+    }
+
+    /// Push token to the lexem while respecting the separator mode
+    #[inline]
+    fn push_token(&mut self, mut token: Token) {
+        if token.kind == TokenKind::Word && self.keywords.contains(&token.word) {
+            token.kind = TokenKind::Keyword;
+        }
+        token.span = self.span.get_span(&self.reader, token.start, token.word.chars().count());
+        if let TokenKind::Region(id) = &token.kind {
+            // Trivia regions are kept in the token stream, but the parser skips them
+            token.trivia = self.region.get_region_by_id(id).is_some_and(|region| region.trivia);
+            token.value = self.cook_region(&token, id);
+        }
+        // Line that begins with trivia holds some code after all
+        let is_code = !token.trivia && !matches!(token.kind, TokenKind::Newline | TokenKind::Indent | TokenKind::Dedent);
+        if let Some((indent, row, start)) = self.pending_indent.take_if(|_| is_code) {
+            if let Err(error) = self.add_indent_level(&indent, row, start) {
+                self.errors.push(error);
+            }
+        }
+        self.separator.handle_token(token, &mut self.lexem);
+    }
+
+    /// Process the word of the region token into its value
+    ///
+    /// Decodes escape sequences and strips the indentation if the region requires it.
+    /// Invalid escape sequences are reported once the current letter has been lexed.
+    fn cook_region(&mut self, token: &Token, id: &str) -> Option<String> {
+        // The closed region holds the delimiters that it has actually been opened and closed with
+        let region = match self.region.get_closed_region() {
+            Some(region) if region.id == id => region,
+            _ => self.region.get_region_by_id(id)?
+        };
+        let escape_symbol = region.escape.get_symbol(self.escape_symbol);
+        let mut value = match (&region.escapes, escape_symbol) {
+            (Some(escapes), Some(escape_symbol)) => {
+                let (value, invalid) = escapes.decode(&token.word, escape_symbol);
+                for escape in invalid {
+                    let pos = get_position_in_token(token, escape.offset, self.reader.tab_width);
+                    let info = PositionInfo::at_pos(self.path.clone(), pos, escape.sequence.chars().count())
+                        .data(escape.sequence);
+                    self.errors.push((LexerErrorType::InvalidEscape, info));
+                }
+                value
+            }
+            _ => token.word.clone()
+        };
+        if region.escape == EscapePolicy::Doubled {
+            // Region continues after an interpolation if it's not the first part of the region
+            let is_continued = matches!(self.lexem.last(), Some(last) if last.kind == TokenKind::RegionEnd);
+            value = collapse_doubled(&value, &region.begin, &region.end, is_continued);
+        }
+        if region.strip_indent || region.delimiter == Delimiter::Indentation {
+            value = strip_common_indent(&value);
+        }
+        (value != token.word).then_some(value)
+    }
+
+    /// Check if the token appears in the code at its span
+    fn is_in_code(&self, token: &Token) -> bool {
+        !token.word.is_empty() && self.reader.get_slice(token.span.start, token.span.end) == Some(token.word.as_str())
+    }
+
+    /// Find the amount of tokens that are not affected by a change of the code at given byte offset
+    ///
+    /// Lexing can restart after a word or a symbol in the global region.
+    /// Such token is safe only if it's followed by another complete token before the change,
+    /// since its end could depend on the letters that follow it.
+    fn find_restart_point(&self, tokens: &[Token], offset: usize) -> usize {
+        let mut depth = 0;
+        let mut candidate = None;
+        let mut restart = 0;
+        for (index, token) in tokens.iter().enumerate() {
+            if !self.is_in_code(token) {
+                continue
+            }
+            if token.span.end >= offset {
+                break
+            }
+            if let Some(candidate) = candidate.take() {
+                restart = candidate + 1;
+            }
+            match token.kind {
+                TokenKind::RegionBegin => depth += 1,
+                TokenKind::RegionEnd => depth -= 1,
+                TokenKind::Word | TokenKind::Keyword | TokenKind::Symbol | TokenKind::Compound if depth == 0 => {
+                    candidate = Some(index);
+                }
+                _ => {}
+            }
+        }
+        restart
+    }
+
+    /// Resume lexing after the tokens that are not affected by a change of the code
+    ///
+    /// The tokens have to come from lexing the code that differs from the current one
+    /// only from given byte offset onwards. Tokens that precede the last safe restart point
+    /// are kept and the lexer continues right after them as if it has lexed them itself.
+    pub fn resume(&mut self, tokens: &[Token], offset: usize) {
+        let restart = self.find_restart_point(tokens, offset);
+        let Some(last) = tokens[..restart].last() else {
+            return
+        };
+        let index = last.start + last.word.chars().count();
+        self.reader.seek(last.span.end, index, last.span.end_pos);
+        self.span.resume(&self.reader, index, last.span.end, last.span.end_pos.0);
+        self.lexem = tokens[..restart].to_vec();
+        self.separator.resume(&self.lexem);
+        if let ScopingMode::Indent = self.scoping_mode {
+            self.indent.resume(&self.lexem, self.reader.get_code());
+        }
+    }
+
+    /// Attach the code between the tokens that have been lexed since the last call to them as trivia
+    ///
+    /// Trivia up to the end of the line belongs to the preceding token and the rest to the following one.
+    /// Tokens that do not appear in the code (or overlap with the preceding token) become synthetic.
+    fn attach_trivia(&mut self) {
+        let mut cursor = self.trivia_cursor;
+        let mut previous = self.trivia_previous;
+        for index in self.trivia_index..self.lexem.len() {
+            let is_in_code = self.is_in_code(&self.lexem[index]);
+            let token = &mut self.lexem[index];
+            let span = token.span;
+            // Trivia region becomes a part of the trivia of the surrounding tokens
+            if token.trivia {
+                continue
+            }
+            if span.start < cursor || !is_in_code {
+                token.span = Span { end: span.start, end_pos: span.start_pos, ..span };
+                token.leading_trivia.clear();
+                token.trailing_trivia.clear();
+                continue
+            }
+            let mut trivia = self.reader.get_slice(cursor, span.start).unwrap_or_default();
+            if let Some(previous) = previous {
+                // The line has already ended if the preceding token is a newline
+                let size = match self.lexem[previous].word.ends_with('\n') {
+                    true => 0,
+                    false => trivia.find('\n').unwrap_or(trivia.len())
+                };
+                self.lexem[previous].trailing_trivia = trivia[..size].to_string();
+                trivia = &trivia[size..];
+            }
+            self.lexem[index].leading_trivia = trivia.to_string();
+            cursor = span.end;
+            previous = Some(index);
+        }
+        self.trivia_cursor = cursor;
+        self.trivia_previous = previous;
+        self.trivia_index = self.lexem.len();
+    }
+
+    /// Attach the rest of the code as trivia once the entire code has been lexed
+    fn finish_trivia(&mut self) {
+        self.attach_trivia();
+        let rest = self.reader.get_code_from(self.trivia_cursor);
+        match self.trivia_previous {
+            Some(previous) => self.lexem[previous].trailing_trivia = rest.to_string(),
+            // Code that consists only of trivia is stored in an empty token
+            None if !rest.is_empty() => {
+                let pos = self.reader.get_position();
+                let start = self.reader.get_index();
+                self.lexem.push(Token {
+                    pos,
+                    start,
+                    kind: TokenKind::Newline,
+                    span: self.span.get_span(&self.reader, start, 0),
+                    leading_trivia: rest.to_string(),
+                    ..Default::default()
+                });
+            }
+            None => {}
         }
     }
 
     /// Add indentation to the lexem
     #[inline]
-    fn add_indent(&mut self, word: String) -> String {
+    fn add_indent(&mut self, word: String) -> Result<String, LexerError> {
         if !word.is_empty() {
             // Getting position by word here would attempt to
             // substract with overflow since the new line character
             // technically belongs to the previous line
             let (row, _col) = self.reader.get_position();
-            self.lexem.push(Token {
+            let indent = word[1..].to_string();
+            let kind = std::mem::take(&mut self.kind);
+            self.push_token(Token {
                 word,
                 pos: (row, 1),
                 start: self.token_start_index,
+                kind,
+                ..Default::default()
             });
             self.position = (0, 0);
-            String::new()
-        } else { word }
+            // Blank lines do not change the indentation level
+            if !self.reader.is_followed_by_newline() {
+                // If there is no indentation the reader is still at the new line character
+                let row = if indent.is_empty() { row + 1 } else { row };
+                let start = self.reader.get_index() + 1;
+                let is_trivia = self.region.is_followed_by_trivia(&self.reader, self.lexem.last());
+                self.measure_indent(indent, row, start, is_trivia)?;
+            }
+            Ok(String::new())
+        } else { Ok(word) }
+    }
+
+    /// Add indentation of the line that follows current new line character
+    ///
+    /// This is used when the new line begins a region, so that the indentation is a part of it.
+    fn add_indent_ahead(&mut self, is_trivia: bool) -> Result<(), LexerError> {
+        let (row, _col) = self.reader.get_position();
+        let line = self.reader.get_rest().get(1..).unwrap_or_default();
+        let code = line.trim_start_matches([' ', '\t']);
+        // Blank lines do not change the indentation level
+        if code.is_empty() || code.starts_with('\n') || code.starts_with("\r\n") {
+            return Ok(())
+        }
+        let indent = line[..line.len() - code.len()].to_string();
+        let start = self.reader.get_index() + 1 + indent.chars().count();
+        self.measure_indent(indent, row + 1, start, is_trivia)
+    }
+
+    /// Add indentation level of the line unless the line begins with trivia
+    ///
+    /// Line that holds only trivia does not change the indentation level,
+    /// so such line is measured once a token that is not trivia appears in it.
+    fn measure_indent(&mut self, indent: String, row: usize, start: usize, is_trivia: bool) -> Result<(), LexerError> {
+        if is_trivia {
+            self.pending_indent = Some((indent, row, start));
+            return Ok(())
+        }
+        self.pending_indent = None;
+        self.add_indent_level(&indent, row, start)
+    }
+
+    /// Add tokens that open or close indentation levels
+    fn add_indent_level(&mut self, indent: &str, row: usize, start: usize) -> Result<(), LexerError> {
+        let size = get_text_width(indent, self.reader.tab_width);
+        let pos = (row, size + 1);
+        if self.mixed_indent == MixedIndentPolicy::Warn && self.indent.is_mixed(indent) {
+            self.warnings.push((
+                LexerErrorType::MixedIndent,
+                PositionInfo::at_pos(self.path.clone(), (row, 1), size).data("indentation")
+            ));
+        }
+        match self.indent.handle_indent(indent) {
+            Ok(IndentReaction::Indent) => {
+                self.push_token(Token { pos, start, kind: TokenKind::Indent, ..Default::default() });
+            }
+            Ok(IndentReaction::Dedent(amount)) => {
+                for _ in 0..amount {
+                    self.push_token(Token { pos, start, kind: TokenKind::Dedent, ..Default::default() });
+                }
+            }
+            Ok(IndentReaction::Keep) => {}
+            Err(kind) => {
+                self.report((
+                    kind,
+                    PositionInfo::at_pos(self.path.clone(), (row, 1), size).data("indentation")
+                ))?;
+            }
+        }
+        Ok(())
     }
 
     /// Add word that has been completed in previous iteration to the lexem
     #[inline]
     fn add_word(&mut self, word: String) -> String {
         if !word.is_empty() {
-            self.lexem.push(Token {
+            let kind = std::mem::take(&mut self.kind);
+            self.push_token(Token {
                 word,
                 pos: self.position,
-                start: self.token_start_index
+                start: self.token_start_index,
+                kind,
+                ..Default::default()
             });
             self.position = (0, 0);
             String::new()
@@ -101,10 +510,13 @@ impl<'a> Lexer<'a> {
     #[inline]
     fn add_word_inclusively(&mut self, word: String) -> String {
         if !word.is_empty() {
-            self.lexem.push(Token {
+            let kind = std::mem::take(&mut self.kind);
+            self.push_token(Token {
                 word,
                 pos: self.position,
-                start: self.token_start_index
+                start: self.token_start_index,
+                kind,
+                ..Default::default()
             });
             self.position = (0, 0);
             String::new()
@@ -112,6 +524,21 @@ impl<'a> Lexer<'a> {
         else { word }
     }
 
+    /// Get the token of the word that is being lexed right now
+    fn get_pending_token(&self, word: &str) -> Token {
+        let kind = match &self.kind {
+            TokenKind::Word if self.keywords.contains(word) => TokenKind::Keyword,
+            kind => kind.clone()
+        };
+        Token {
+            word: word.to_string(),
+            pos: self.position,
+            start: self.token_start_index,
+            kind,
+            ..Default::default()
+        }
+    }
+
     /// Checks whether this is a nontokenizable region
     #[inline]
     pub fn is_tokenized_region(&self, reaction: &RegionReaction) -> bool {
@@ -124,12 +551,13 @@ impl<'a> Lexer<'a> {
     /// Pattern code for adding a symbol
     /// **[*]**
     #[inline]
-    fn pattern_add_symbol(&mut self, mut word: String, letter: char) -> String {
+    fn pattern_add_symbol(&mut self, mut word: String, letter: char, kind: TokenKind) -> String {
         word = self.add_word(word);
         if word.is_empty() {
             self.token_start_index = self.index;
         }
         self.word_push(&mut word, letter);
+        self.kind = if letter == '\n' { TokenKind::Newline } else { kind };
         self.position = self.reader.get_position();
         self.add_word_inclusively(word)
     }
@@ -139,10 +567,40 @@ impl<'a> Lexer<'a> {
     #[inline]
     fn pattern_begin(&mut self, mut word: String, letter: char) -> String {
         word = self.add_word(word);
+        // The previous word has reset the position of the one that begins here
+        self.position = self.reader.get_position();
         self.word_push(&mut word, letter);
         word
     }
 
+    /// Pattern code for a new line that begins the indentation of the next line
+    /// **\n**
+    #[inline]
+    fn pattern_indent(&mut self, word: String, letter: char) -> Result<String, LexerError> {
+        self.is_indenting = true;
+        let word = self.pattern_begin(word, letter);
+        self.kind = TokenKind::Newline;
+        self.pattern_indent_end(word)
+    }
+
+    /// Pattern code for concluding the indentation if the next letter is not a part of it
+    #[inline]
+    fn pattern_indent_end(&mut self, mut word: String) -> Result<String, LexerError> {
+        if let Some(next_char) = self.reader.peek() {
+            if ![' ', '\t'].contains(&next_char) {
+                word = self.add_indent(word)?;
+                self.is_indenting = false;
+            }
+        }
+        Ok(word)
+    }
+
+    /// Checks if a new line in current region begins the indentation of the next line
+    #[inline]
+    fn is_indent_measured(&self) -> bool {
+        self.scoping_mode == ScopingMode::Indent && self.region.get_region().is_some_and(|region| region.tokenize)
+    }
+
     /// Pattern code for ending current region
     /// **]**
     #[inline]
@@ -155,179 +613,527 @@ impl<'a> Lexer<'a> {
     fn word_push(&mut self, word: &mut String, letter: char) {
         if word.is_empty() {
             self.token_start_index = self.index;
+            self.kind = self.get_word_kind();
         }
         word.push(letter);
     }
 
-    /// Tokenize source code
+    /// Get kind of the word that begins in the current region
+    #[inline]
+    fn get_word_kind(&self) -> TokenKind {
+        match self.region.get_region() {
+            Some(region) if !region.tokenize => TokenKind::Region(region.id.clone()),
+            _ => TokenKind::Word
+        }
+    }
+
+    /// Try to lex the token with custom matchers
     ///
-    /// Run lexer and tokenize code. The result is stored in the lexem attribute
-    pub fn run(&mut self) -> Result<(), LexerError> {
-        let mut word = String::new();
-        let mut is_indenting = false;
-        while let Some(letter) = self.reader.next() {
-            self.index = self.reader.get_index();
-
-            /****************/
-            /* Set Position */
-            /****************/
-
-            // If the new position hasn't been set yet, set it
-            if self.position == (0, 0) {
-                // If separator mode is set to Manual and the letter is a separator,
-                // then skip finding a new position
-                if SeparatorMode::Manual != self.separator_mode || letter != '\n' {
-                    let region = self.region.get_region().unwrap();
-                    // If the region is tokenized, then check if the letter is a separator
-                    if !region.tokenize || !vec![' ', '\t'].contains(&letter) {
-                        self.position = self.reader.get_position();
-                    }
+    /// Returns the matched token if any of the matchers succeeded.
+    /// Letters of the token are checked against the allowed classes as any other word.
+    fn pattern_matcher(&mut self, mut word: String, letter: char) -> Result<Option<String>, LexerError> {
+        let code = self.reader.get_rest();
+        let Some(size) = self.matchers.iter().find_map(|matcher| matcher.matches(code)) else {
+            return Ok(None)
+        };
+        let matched = code[..size].to_string();
+        self.check_char(letter)?;
+        self.word_push(&mut word, letter);
+        for letter in matched.chars().skip(1) {
+            self.reader.next();
+            self.check_char(letter)?;
+            word.push(letter);
+        }
+        Ok(Some(self.add_word_inclusively(word)))
+    }
+
+    /// Match continuator that ends current line
+    ///
+    /// Returns the amount of characters that the continuation spans
+    /// (excluding currently processed character) if the continuator was found
+    fn match_continuator(&self) -> Result<Option<usize>, LexerError> {
+        let continuator = match &self.separator_mode {
+            SeparatorMode::Automatic(continuator) if !continuator.is_empty() => continuator,
+            _ => return Ok(None)
+        };
+        let size = continuator.chars().count();
+        if self.reader.get_future(size).as_ref() != Some(continuator) {
+            return Ok(None)
+        }
+        let mut is_newline = false;
+        let mut offset = size;
+        // Only whitespace can follow the continuator
+        while let Some(future) = self.reader.get_future(offset + 1) {
+            match future.chars().last() {
+                Some(' ' | '\t') => {},
+                // Carriage return belongs to the line ending
+                Some('\r') if !is_newline => {},
+                Some('\n') if !is_newline => is_newline = true,
+                // This is the beginning of the next line
+                Some(_) if is_newline => return Ok(Some(offset - 1)),
+                _ => return Ok(None)
+            }
+            offset += 1;
+        }
+        let pos = self.reader.get_position();
+        Err((
+            LexerErrorType::DanglingContinuator,
+            PositionInfo::at_pos(self.path.clone(), pos, size).data(format!("continuator '{continuator}'"))
+        ))
+    }
+
+    /// Skip the continuator, the following newline and the indentation
+    /// of the continued line as it should not produce an indent token
+    fn skip_continuation(&mut self, offset: usize) {
+        for _ in 0..offset {
+            self.reader.next();
+        }
+        self.position = (0, 0);
+    }
+
+    /// Check if the letter is whitespace that separates tokens
+    /// (carriage return that precedes a new line belongs to the line ending)
+    #[inline]
+    fn is_whitespace(&self, letter: char) -> bool {
+        match letter {
+            ' ' | '\t' => true,
+            '\r' => self.reader.is_followed_by_newline(),
+            _ => false
+        }
+    }
+
+    /// Report the letter of a word if it does not belong to any of the allowed classes
+    fn check_char(&mut self, letter: char) -> Result<(), LexerError> {
+        if self.allowed_chars.is_empty() || self.allowed_chars.iter().any(|class| class.contains(letter)) {
+            return Ok(())
+        }
+        let pos = self.reader.get_position();
+        self.report((
+            LexerErrorType::InvalidCharacter(letter),
+            PositionInfo::at_pos(self.path.clone(), pos, 1).data(format!("U+{:04X}", letter as u32))
+        ))
+    }
+
+    /// Skip the byte order mark and the shebang line at the beginning of the code
+    ///
+    /// Returns true if the letter has been skipped. The new line that ends the shebang is lexed as usual.
+    fn skip_preamble(&mut self, letter: char) -> bool {
+        if letter == '\u{FEFF}' && self.index == 0 {
+            self.bom = true;
+            return true
+        }
+        let is_beginning = self.index == usize::from(self.bom);
+        if is_beginning && self.allow_shebang && letter == '#' && self.reader.peek() == Some('!') {
+            let mut shebang = String::from(letter);
+            while self.reader.peek().is_some() && !self.reader.is_followed_by_newline() {
+                shebang.extend(self.reader.next());
+            }
+            self.shebang = Some(shebang);
+            return true
+        }
+        false
+    }
+
+    /// Lex the letter that has been read
+    ///
+    /// Takes the word that is being lexed and returns it after this letter has been processed
+    fn lex_letter(&mut self, mut word: String, letter: char) -> Result<String, LexerError> {
+        self.index = self.reader.get_index();
+        if self.index <= usize::from(self.bom) && self.skip_preamble(letter) {
+            return Ok(word)
+        }
+
+        // Region that ends with indentation closes before the line that is not indented enough
+        if letter == '\n' && self.region.is_dedented(&self.reader) {
+            self.region.close_region();
+            word = self.add_word(word);
+            self.is_escaped = false;
+        }
+
+        /****************/
+        /* Set Position */
+        /****************/
+
+        // If the new position hasn't been set yet, set it
+        if self.position == (0, 0) {
+            // If separator mode is set to Manual and the letter is a separator,
+            // then skip finding a new position
+            if SeparatorMode::Manual != self.separator_mode || letter != '\n' {
+                let region = self.region.get_region().unwrap();
+                // If the region is tokenized, then check if the letter is a separator
+                if !region.tokenize || !self.is_whitespace(letter) {
+                    self.position = self.reader.get_position();
                 }
             }
+        }
 
-            // Reaction stores the reaction of the region handler
-            // Have we just opened or closed some region?
-            let reaction = self.region.handle_region(&self.reader, self.is_escaped);
-            match reaction {
-                // If the region has been opened
-                // Finish the part that we have been parsing
-                RegionReaction::Begin(tokenize) => {
-                    // Also if the new region is an interpolation that tokenizes
-                    // the inner content - separate the region from the content
-                    if tokenize {
-                        word = self.pattern_add_symbol(word, letter);
-                    }
-                    // Regular region case
-                    else {
-                        // This is supposed to prevent overshadowing new line
-                        // character if region rule opens with newline
-                        if letter == '\n' {
-                            // This additionally creates a new token
-                            word = self.pattern_add_symbol(word, letter);
+        // Reaction stores the reaction of the region handler
+        // Have we just opened or closed some region?
+        // Guards of the regions look at the token that precedes current letter
+        let is_guarded = self.region.is_guarded();
+        let is_indent_measured = letter == '\n' && self.is_indent_measured();
+        let pending = (is_guarded && !word.is_empty()).then(|| self.get_pending_token(&word));
+        let previous = pending.as_ref().or(self.lexem.last()).filter(|_| is_guarded);
+        let reaction = self.region.handle_region(&self.reader, self.is_escaped, previous);
+        if let Some(reference) = self.region.take_dangling_reference() {
+            let name = self.region.get_region().map(|region| region.name.clone()).unwrap_or_default();
+            self.report((
+                LexerErrorType::DanglingReference(reference),
+                PositionInfo::at_pos(self.path.clone(), self.reader.get_position(), 1).data(name)
+            ))?;
+        }
+        match reaction {
+            // If the region has been opened
+            // Finish the part that we have been parsing
+            RegionReaction::Begin(tokenize) => {
+                // Also if the new region is an interpolation that tokenizes
+                // the inner content - separate the region from the content
+                if tokenize {
+                    word = self.pattern_add_symbol(word, letter, TokenKind::RegionBegin);
+                }
+                // Regular region case
+                else {
+                    // This is supposed to prevent overshadowing new line
+                    // character if region rule opens with newline
+                    if letter == '\n' {
+                        // This additionally creates a new token
+                        word = self.pattern_add_symbol(word, letter, TokenKind::Newline);
+                        // The indentation of the next line belongs to the region,
+                        // so it has to be measured before it's read
+                        if is_indent_measured {
+                            let is_trivia = self.region.get_region().is_some_and(|region| region.trivia);
+                            self.add_indent_ahead(is_trivia)?;
                         }
-                        // Normally start a new region
-                        word = self.pattern_begin(word, letter);
                     }
-                },
-                // If the region has been closed
-                // Add the closing region and finish the word
-                RegionReaction::End(tokenize) => {
-                    // Also if the new region is an interpolation that tokenizes
-                    // the inner content - separate the region from the content
-                    if tokenize {
-                        word = self.pattern_add_symbol(word, letter);
+                    // Normally start a new region
+                    word = self.pattern_begin(word, letter);
+                }
+            },
+            // If the region has been closed
+            // Add the closing region and finish the word
+            RegionReaction::End(tokenize) => {
+                // Also if the new region is an interpolation that tokenizes
+                // the inner content - separate the region from the content
+                if tokenize {
+                    word = self.pattern_add_symbol(word, letter, TokenKind::RegionEnd);
+                }
+                // Regular region case
+                else {
+                    // Normally close the region
+                    self.word_push(&mut word, letter);
+                    // The closed region is no longer on the stack
+                    if let Some(region) = self.region.get_closed_region() {
+                        self.kind = TokenKind::Region(region.id.clone());
                     }
-                    // Regular region case
-                    else {
-                        // Normally close the region
-                        word = self.pattern_end(word, letter);
-                        // This is supposed to prevent overshadowing new line
-                        // character if region rule closes with newline
-                        if letter == '\n' {
+                    word = self.add_word_inclusively(word);
+                    // This is supposed to prevent overshadowing new line
+                    // character if region rule closes with newline
+                    if letter == '\n' {
+                        // The new line begins the indentation of the next line
+                        // just like it would if it was not a part of the region
+                        if self.is_indent_measured() {
+                            word = self.pattern_indent(word, letter)?;
+                        }
+                        else {
                             // This additionally creates a new token
-                            word = self.pattern_add_symbol(word, letter);
+                            word = self.pattern_add_symbol(word, letter, TokenKind::Newline);
                         }
                     }
                 }
-                RegionReaction::Pass => {
-                    match self.compound.handle_compound(letter, &self.reader, self.is_tokenized_region(&reaction)) {
-                        CompoundReaction::Begin => word = self.pattern_begin(word, letter),
-                        CompoundReaction::Keep => self.word_push(&mut word, letter),
-                        CompoundReaction::End => word = self.pattern_end(word, letter),
-                        CompoundReaction::Pass => {
-                            // Handle region scope
-                            if !self.is_tokenized_region(&reaction) {
-                                let region = self.region.get_region().unwrap();
-                                // Flip escaped key
-                                self.is_escaped = (!self.is_escaped && letter == self.escape_symbol)
-                                    .then(|| !self.is_escaped)
-                                    .unwrap_or(false);
-                                // Handle singleline attribute
-                                if letter == '\n' && region.singleline {
-                                    let pos = self.reader.get_position();
-                                    return Err((
-                                        LexerErrorType::Singleline,
-                                        PositionInfo::at_pos(self.path.clone(), pos, 0).data(region.name.clone())
-                                    ))
-                                }
-                                self.word_push(&mut word, letter);
+            }
+            RegionReaction::Pass => {
+                // Join the next line if this one ends with a continuator
+                if self.is_tokenized_region(&reaction) {
+                    match self.match_continuator() {
+                        Ok(Some(offset)) => {
+                            word = self.add_word(word);
+                            self.skip_continuation(offset);
+                            return Ok(word)
+                        }
+                        Ok(None) => {}
+                        // Dangling continuator is lexed as a regular token
+                        Err(error) => self.report(error)?
+                    }
+                }
+                // Custom matchers can begin a token where the previous one has ended
+                let is_matchable = word.is_empty() && !self.matchers.is_empty() && ![' ', '\t', '\n'].contains(&letter);
+                if is_matchable && self.is_tokenized_region(&reaction) {
+                    if let Some(new_word) = self.pattern_matcher(String::new(), letter)? {
+                        word = new_word;
+                        return Ok(word)
+                    }
+                }
+                match self.compound.handle_compound(letter, &self.reader, self.is_tokenized_region(&reaction)) {
+                    CompoundReaction::Begin => {
+                        word = self.pattern_begin(word, letter);
+                        self.kind = TokenKind::Compound;
+                    },
+                    CompoundReaction::Keep => self.word_push(&mut word, letter),
+                    CompoundReaction::End => word = self.pattern_end(word, letter),
+                    CompoundReaction::Pass => {
+                        // Handle singleline attribute
+                        if letter == '\n' && !self.is_tokenized_region(&reaction) {
+                            let region = self.region.get_region().unwrap();
+                            if region.singleline {
+                                let pos = self.reader.get_position();
+                                let name = region.name.clone();
+                                self.report((
+                                    LexerErrorType::Singleline,
+                                    PositionInfo::at_pos(self.path.clone(), pos, 0).data(name)
+                                ))?;
+                                // Close the spilled region and lex the new line outside of it
+                                word = self.add_word(word);
+                                self.region.close_region();
+                                self.is_escaped = false;
                             }
-                            else {
-
-                                /******************/
-                                /* Mode modifiers */
-                                /******************/
-
-                                // Create indent regions: '\n   '
-                                if let ScopingMode::Indent = self.scoping_mode {
-                                    // If we are still in the indent region - proceed
-                                    if is_indenting && vec![' ', '\t'].contains(&letter) {
-                                        self.word_push(&mut word, letter);
-                                    }
-                                    // If it's the new line - start indent region
-                                    if letter == '\n' {
-                                        is_indenting = true;
-                                        word = self.pattern_begin(word, letter);
-                                    }
-                                    // Check if the current letter
-                                    // concludes current indent region
-                                    if is_indenting {
-                                        if let Some(next_char) = self.reader.peek() {
-                                            if !vec![' ', '\t'].contains(&next_char) {
-                                                word = self.add_indent(word);
-                                                is_indenting = false;
-                                            }
-                                        }
-                                        continue
-                                    }
-                                }
-                                // Skip newline character if we want to manually insert semicolons
-                                if let SeparatorMode::Manual = self.separator_mode {
-                                    if letter == '\n' {
-                                        word = self.add_word(word);
-                                        continue
-                                    }
-                                }
+                        }
+                        // Handle region scope
+                        if !self.is_tokenized_region(&reaction) {
+                            // Flip escaped key
+                            let region = self.region.get_region().unwrap();
+                            let escape_symbol = region.escape.get_symbol(self.escape_symbol);
+                            self.is_escaped = !self.is_escaped && Some(letter) == escape_symbol;
+                            self.word_push(&mut word, letter);
+                        }
+                        else {
 
-                                /*****************/
-                                /* Regular Lexer */
-                                /*****************/
+                            /******************/
+                            /* Mode modifiers */
+                            /******************/
 
-                                // Skip whitespace
-                                if vec![' ', '\t'].contains(&letter) {
-                                    word = self.add_word(word);
+                            // Create indent regions: '\n   '
+                            if let ScopingMode::Indent = self.scoping_mode {
+                                // If we are still in the indent region - proceed
+                                if self.is_indenting && [' ', '\t'].contains(&letter) {
+                                    self.word_push(&mut word, letter);
                                 }
-                                // Handle special symbols
-                                else if self.symbols.contains(&letter) || letter == '\n' {
-                                    word = self.pattern_add_symbol(word, letter);
+                                // If it's the new line - start indent region
+                                if letter == '\n' {
+                                    return self.pattern_indent(word, letter)
                                 }
-                                // Handle word
-                                else {
-                                    self.word_push(&mut word, letter);
+                                // Check if the current letter
+                                // concludes current indent region
+                                if self.is_indenting {
+                                    return self.pattern_indent_end(word)
                                 }
                             }
+                            // Skip newline character if we want to manually insert semicolons
+                            if let SeparatorMode::Manual = self.separator_mode {
+                                if letter == '\n' {
+                                    word = self.add_word(word);
+                                    return Ok(word)
+                                }
+                            }
+
+                            /*****************/
+                            /* Regular Lexer */
+                            /*****************/
+
+                            // Skip whitespace
+                            if self.is_whitespace(letter) {
+                                word = self.add_word(word);
+                            }
+                            // Handle special symbols
+                            else if self.symbols.contains(&letter) || letter == '\n' {
+                                word = self.pattern_add_symbol(word, letter, TokenKind::Symbol);
+                            }
+                            // Handle word
+                            else {
+                                self.check_char(letter)?;
+                                self.word_push(&mut word, letter);
+                            }
                         }
                     }
                 }
             }
         }
+        Ok(word)
+    }
+
+    /// Finish lexing once the entire code has been read
+    fn finish(&mut self) -> Result<(), LexerError> {
+        if let Some(error) = self.reader.take_error() {
+            let pos = self.reader.get_position();
+            self.report((
+                LexerErrorType::InputError,
+                PositionInfo::at_pos(self.path.clone(), pos, 0).data(error.to_string())
+            ))?;
+        }
+        // Close the regions that end along with the code
+        while self.region.get_region().is_some_and(|region| region.end_at_eof || region.delimiter == Delimiter::Indentation) {
+            if self.region.close_region().is_none() {
+                break
+            }
+        }
+        let word = std::mem::take(&mut self.word);
         self.add_word(word);
+        self.check_errors()?;
+        // Insert the last separator if it's missing
+        let pos = self.reader.get_position();
+        let start = self.reader.get_index();
+        let eof = Token {
+            word: String::from("\n"),
+            pos,
+            start,
+            kind: TokenKind::Newline,
+            span: self.span.get_span(&self.reader, start, 0),
+            ..Default::default()
+        };
+        self.separator.finish(eof, &mut self.lexem);
+        // Close all indentation levels that were left open
+        for _ in 0..self.indent.finish() {
+            self.push_token(Token {
+                pos,
+                start,
+                kind: TokenKind::Dedent,
+                ..Default::default()
+            });
+        }
+        if self.lossless {
+            self.finish_trivia();
+        }
         // If some region exists that was not closed
-        if let Err((pos, region)) = self.region.is_region_closed(&self.reader) {
-            return Err((
-                LexerErrorType::Unclosed,
-                PositionInfo::at_pos(self.path.clone(), pos, 0).data(region.name)
-            ));
+        while let Err((pos, name)) = self.region.is_region_closed(&self.reader) {
+            let opened_at = self.region.get_opened_at();
+            let kind = if opened_at.len() > 1 {
+                LexerErrorType::UnclosedNested(opened_at.to_vec())
+            } else {
+                LexerErrorType::Unclosed
+            };
+            self.report((
+                kind,
+                PositionInfo::at_pos(self.path.clone(), pos, 0).data(name)
+            ))?;
+            if self.region.close_region().is_none() {
+                break
+            }
+        }
+        self.check_errors()
+    }
+
+    /// Lex the next letter or finish lexing if the entire code has been read
+    ///
+    /// Returns false once lexing has finished
+    fn step(&mut self) -> Result<bool, LexerError> {
+        self.check_errors()?;
+        match self.reader.next() {
+            Some(letter) => {
+                let word = std::mem::take(&mut self.word);
+                self.word = self.lex_letter(word, letter)?;
+                // The code that has been lexed is no longer needed
+                if letter == '\n' && self.word.is_empty() {
+                    let mut offset = self.span.get_offset();
+                    if self.lossless {
+                        offset = offset.min(self.trivia_cursor);
+                    }
+                    self.reader.release(offset);
+                }
+                Ok(true)
+            }
+            None => {
+                self.finish()?;
+                Ok(false)
+            }
         }
+    }
+
+    /// Tokenize source code
+    ///
+    /// Run lexer and tokenize code. The result is stored in the lexem attribute
+    pub fn run(&mut self) -> Result<(), LexerError> {
+        while self.step()? {}
         Ok(())
     }
+
+    /// Move the tokens that will not change anymore to the queue of the iterator
+    fn flush(&mut self) {
+        let ready = if self.is_finished {
+            self.lexem.len()
+        } else {
+            // Tokens that await trivia and the last token (that the next one can depend on) are kept
+            if self.lossless {
+                self.attach_trivia();
+            }
+            let kept = if self.lossless { self.trivia_previous.unwrap_or(0) } else { self.lexem.len() };
+            kept.min(self.lexem.len().saturating_sub(1))
+        };
+        self.queue.extend(self.lexem.drain(..ready).map(Ok));
+        self.trivia_index -= ready.min(self.trivia_index);
+        self.trivia_previous = self.trivia_previous.and_then(|previous| previous.checked_sub(ready));
+    }
+
+    /// Tokenize source code and recover from errors
+    ///
+    /// Instead of stopping at the first error, the lexer closes the offending region
+    /// (or skips the offending indentation), records the error and keeps lexing.
+    /// The result is stored in the lexem attribute and all the errors are returned.
+    pub fn run_recovering(&mut self) -> Vec<LexerError> {
+        self.is_recovering = true;
+        // Recovering lexer never stops with an error
+        let _ = self.run();
+        std::mem::take(&mut self.errors)
+    }
+}
+
+/// Streaming lexer that yields the tokens as soon as they are lexed
+///
+/// Lexing stops at the first error which is yielded after the tokens that precede it.
+/// Tokens that have been yielded are not stored in the lexem attribute.
+///
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let rules = Rules::new(vec!['+'], vec![], reg![]);
+/// let cc = Compiler::new("HerbScript", rules);
+/// let input = std::io::Cursor::new("a + b");
+/// let words = Lexer::from_read(&cc, input)
+///     .map(|token| token.map(|token| token.word))
+///     .collect::<Result<Vec<_>, _>>();
+/// assert_eq!(words.unwrap(), vec!["a", "+", "b"]);
+/// ```
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.queue.pop_front() {
+                return Some(item)
+            }
+            if self.is_finished {
+                return None
+            }
+            match self.step() {
+                Ok(is_running) => {
+                    self.is_finished = !is_running;
+                    self.flush();
+                }
+                // Tokens that have been lexed before the error are yielded first
+                Err(error) => {
+                    self.is_finished = true;
+                    self.flush();
+                    self.queue.push_back(Err(error));
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::compiling_rules::{ Region, Rules };
+    use crate::compiling_rules::{ CharClass, Delimiter, EscapePolicy, EscapeTable, Region, RegionGuard, Rules, TokenMatcher };
+    use crate::compiling::failing::position_info::Position;
+    use super::LexerErrorType;
     use crate::reg;
-    use crate::compiling::{ Compiler, ScopingMode };
+    use crate::compiling::{ Compiler, DefaultMetadata, Metadata, MixedIndentPolicy, Preamble, ScopingMode, SeparatorMode, TextEdit, Token, TokenKind };
+
+    // Get the word of the token with indentation levels written out
+    fn get_word(token: &Token) -> String {
+        match token.kind {
+            TokenKind::Indent => String::from("<indent>"),
+            TokenKind::Dedent => String::from("<dedent>"),
+            _ => token.word.clone()
+        }
+    }
 
     #[test]
     fn test_lexer_base() {
@@ -411,42 +1217,195 @@ mod test {
     }
 
     #[test]
-    fn test_lexer_indent_scoping_mode() {
-        let symbols = vec![':'];
-        let regions = reg![];
+    fn test_lexer_token_kinds() {
+        let symbols = vec!['+'];
+        let regions = reg![
+            reg!(string_literal as "String literal" => {
+                begin: "'",
+                end: "'"
+            } => [
+                reg!(string_interp as "String interpolation" => {
+                    begin: "{",
+                    end: "}",
+                    tokenize: true
+                } ref global)
+            ])
+        ];
+        let string = TokenKind::Region("string_literal".to_string());
         let expected = vec![
-            ("if".to_string(), (1, 1), 0),
-            ("condition".to_string(), (1, 4), 3),
-            (":".to_string(), (1, 13), 12),
-            ("\n    ".to_string(), (2, 1), 13),
-            ("if".to_string(), (2, 5), 18),
-            ("subcondition".to_string(), (2, 8), 21),
-            (":".to_string(), (2, 20), 33),
-            ("\n        ".to_string(), (3, 1), 34),
-            ("pass".to_string(), (3, 9), 43)
+            ("let".to_string(), TokenKind::Keyword),
+            ("a".to_string(), TokenKind::Word),
+            ("++".to_string(), TokenKind::Compound),
+            ("+".to_string(), TokenKind::Symbol),
+            ("'+".to_string(), string.clone()),
+            ("{".to_string(), TokenKind::RegionBegin),
+            ("b".to_string(), TokenKind::Word),
+            ("}".to_string(), TokenKind::RegionEnd),
+            ("'".to_string(), string),
+            ("\n".to_string(), TokenKind::Newline)
         ];
-        let rules = Rules::new(symbols, vec![], regions);
-        let mut cc: Compiler = Compiler::new("Testhon", rules);
-        cc.scoping_mode = ScopingMode::Indent;
-        cc.load(vec![
-            "if condition:",
-            "    if subcondition:",
-            "        pass"
-        ].join("\n"));
+        let rules = Rules::new(symbols, vec![('+', '+')], regions)
+            .set_keywords(vec!["let"]);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.separator_mode = SeparatorMode::Automatic("\\".to_string());
+        cc.load("let a ++ + '+{b}'\n");
         let mut lexer = super::Lexer::new(&cc);
         let mut result = vec![];
         // Simulate lexing
         let res = lexer.run();
         assert!(res.is_ok());
         for lex in lexer.lexem {
-            result.push((lex.word, (lex.pos.0, lex.pos.1), lex.start));
+            result.push((lex.word, lex.kind));
         }
         assert_eq!(expected, result);
     }
 
     #[test]
-    fn test_lexer_manual_separator_mode() {
-        let symbols = vec![';', '+', '='];
+    fn test_lexer_token_matchers() {
+        let symbols = vec!['.', '-', '$', '+'];
+        let number = TokenMatcher::pattern("number", "[0-9]+(\\.[0-9]+)?(e-?[0-9]+)?");
+        let sigil = TokenMatcher::new("sigil", |code| {
+            let name = code.strip_prefix('$')?;
+            let size = name.chars().take_while(|letter| letter.is_alphanumeric()).count();
+            (size > 0).then_some(size + 1)
+        });
+        let expected = vec![
+            ("$var".to_string(), (1, 1)),
+            ("+".to_string(), (1, 6)),
+            ("12.24".to_string(), (1, 8)),
+            ("-".to_string(), (1, 13)),
+            ("1e-5".to_string(), (1, 14)),
+            ("+".to_string(), (1, 19)),
+            ("a".to_string(), (1, 21)),
+            (".".to_string(), (1, 22)),
+            ("b".to_string(), (1, 23))
+        ];
+        let rules = Rules::new(symbols, vec![], reg![])
+            .add_matcher(number)
+            .add_matcher(sigil);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("$var + 12.24-1e-5 + a.b");
+        let mut lexer = super::Lexer::new(&cc);
+        let mut result = vec![];
+        // Simulate lexing
+        let res = lexer.run();
+        assert!(res.is_ok());
+        for lex in lexer.lexem {
+            result.push((lex.word, lex.pos));
+        }
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_lexer_spans() {
+        let symbols = vec!['='];
+        let regions = reg![
+            reg!(string as "String literal" => {
+                begin: "'",
+                end: "'"
+            })
+        ];
+        let expected = vec![
+            ("ść".to_string(), 0..4, (1, 1), (1, 3), Some(1)),
+            ("=".to_string(), 5..6, (1, 4), (1, 5), Some(4)),
+            ("'𝄞'".to_string(), 7..13, (1, 6), (1, 9), Some(6)),
+            ("x".to_string(), 14..15, (2, 1), (2, 2), Some(1))
+        ];
+        let rules = Rules::new(symbols, vec![], regions);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("ść = '𝄞'\nx");
+        let mut lexer = super::Lexer::new(&cc);
+        let mut result = vec![];
+        // Simulate lexing
+        let res = lexer.run();
+        assert!(res.is_ok());
+        for lex in lexer.lexem {
+            let span = lex.span;
+            result.push((lex.word, span.start..span.end, span.start_pos, span.end_pos, span.utf16_col));
+        }
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_lexer_indent_scoping_mode() {
+        let symbols = vec![':'];
+        let regions = reg![];
+        let expected = vec![
+            ("if".to_string(), (1, 1), 0),
+            ("condition".to_string(), (1, 4), 3),
+            (":".to_string(), (1, 13), 12),
+            ("\n    ".to_string(), (2, 1), 13),
+            ("<indent>".to_string(), (2, 5), 18),
+            ("if".to_string(), (2, 5), 18),
+            ("subcondition".to_string(), (2, 8), 21),
+            (":".to_string(), (2, 20), 33),
+            ("\n        ".to_string(), (3, 1), 34),
+            ("<indent>".to_string(), (3, 9), 43),
+            ("pass".to_string(), (3, 9), 43),
+            ("\n".to_string(), (3, 1), 47),
+            ("\n".to_string(), (4, 1), 48),
+            ("<dedent>".to_string(), (5, 1), 49),
+            ("<dedent>".to_string(), (5, 1), 49),
+            ("end".to_string(), (5, 1), 49)
+        ];
+        let rules = Rules::new(symbols, vec![], regions);
+        let mut cc: Compiler = Compiler::new("Testhon", rules);
+        cc.scoping_mode = ScopingMode::Indent;
+        cc.load(vec![
+            "if condition:",
+            "    if subcondition:",
+            "        pass",
+            "",
+            "end"
+        ].join("\n"));
+        let mut lexer = super::Lexer::new(&cc);
+        let mut result = vec![];
+        // Simulate lexing
+        let res = lexer.run();
+        assert!(res.is_ok());
+        for lex in lexer.lexem {
+            result.push((get_word(&lex), (lex.pos.0, lex.pos.1), lex.start));
+        }
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_lexer_indent_after_regions() {
+        let symbols = vec![':'];
+        let regions = reg![
+            reg!(comment as "Comment" => {
+                begin: "#",
+                end: "\n"
+            }),
+            reg!(heading as "Heading" => {
+                begin: "\n>",
+                end: "<"
+            })
+        ];
+        let rules = Rules::new(symbols, vec![], regions);
+        let mut cc: Compiler = Compiler::new("Testhon", rules);
+        cc.use_indents();
+        let mut lex = |code: &str| {
+            cc.load(code);
+            cc.tokenize().unwrap().iter().map(get_word).collect::<Vec<_>>()
+        };
+        // Comment that ends the line
+        assert_eq!(lex("a:\n    b # x\nd\n"), vec![
+            "a", ":", "\n    ", "<indent>", "b", "# x\n", "\n", "<dedent>", "d", "\n"
+        ]);
+        // Comment that takes the entire line
+        assert_eq!(lex("a:\n    b\n    # c\n        c\nd\n"), vec![
+            "a", ":", "\n    ", "<indent>", "b", "\n    ", "# c\n", "\n        ", "<indent>", "c", "\n", "<dedent>", "<dedent>", "d", "\n"
+        ]);
+        // Region that begins with the new line
+        assert_eq!(lex("a:\n    b\n>h<\n    c\n"), vec![
+            "a", ":", "\n    ", "<indent>", "b", "\n", "<dedent>", "\n>h<", "\n    ", "<indent>", "c", "\n", "<dedent>"
+        ]);
+    }
+
+    #[test]
+    fn test_lexer_manual_separator_mode() {
+        let symbols = vec![';', '+', '='];
         let regions = reg![];
         let expected = vec![
             ("let".to_string(), 1, 1),
@@ -475,6 +1434,1001 @@ mod test {
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn test_lexer_semi_automatic_separator_mode() {
+        let symbols = vec![';', '+', '=', '(', ')', ','];
+        let regions = reg![];
+        let expected = vec![
+            ("let".to_string(), 1, 1),
+            ("age".to_string(), 1, 5),
+            ("=".to_string(), 1, 9),
+            ("12".to_string(), 1, 11),
+            ("+".to_string(), 1, 14),
+            ("12".to_string(), 2, 1),
+            (";".to_string(), 2, 3),
+            ("foo".to_string(), 3, 1),
+            ("(".to_string(), 3, 4),
+            ("age".to_string(), 4, 5),
+            (",".to_string(), 4, 8),
+            ("1".to_string(), 5, 5),
+            (")".to_string(), 6, 1),
+            (";".to_string(), 6, 2)
+        ];
+        let rules = Rules::new(symbols, vec![], regions);
+        let mut cc: Compiler = Compiler::new("Testscript", rules);
+        cc.separator_mode = SeparatorMode::SemiAutomatic(";".to_string());
+        cc.load([
+            "let age = 12 +",
+            "12",
+            "foo(",
+            "    age,",
+            "    1",
+            ")"
+        ].join("\n"));
+        let mut lexer = super::Lexer::new(&cc);
+        let mut result = vec![];
+        // Simulate lexing
+        let res = lexer.run();
+        assert!(res.is_ok());
+        for lex in lexer.lexem {
+            result.push((lex.word, lex.pos.0, lex.pos.1));
+        }
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_lexer_automatic_separator_mode() {
+        let symbols = vec![':', '+'];
+        let regions = reg![];
+        let expected = vec![
+            ("if".to_string(), 1, 1),
+            ("a".to_string(), 1, 4),
+            ("+".to_string(), 1, 6),
+            ("b".to_string(), 2, 9),
+            (":".to_string(), 2, 10),
+            ("\n    ".to_string(), 3, 1),
+            ("<indent>".to_string(), 3, 5),
+            ("pass".to_string(), 3, 5),
+            ("<dedent>".to_string(), 3, 9)
+        ];
+        let rules = Rules::new(symbols, vec![], regions);
+        let mut cc: Compiler = Compiler::new("Testhon", rules);
+        cc.scoping_mode = ScopingMode::Indent;
+        cc.separator_mode = SeparatorMode::Automatic("\\".to_string());
+        cc.load([
+            "if a + \\  ",
+            "        b:",
+            "    pass"
+        ].join("\n"));
+        let mut lexer = super::Lexer::new(&cc);
+        let mut result = vec![];
+        // Simulate lexing
+        let res = lexer.run();
+        assert!(res.is_ok());
+        for lex in lexer.lexem {
+            result.push((get_word(&lex), lex.pos.0, lex.pos.1));
+        }
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_lexer_dangling_continuator() {
+        let rules = Rules::new(vec![], vec![], reg![]);
+        let mut cc: Compiler = Compiler::new("Testhon", rules);
+        cc.separator_mode = SeparatorMode::Automatic("..".to_string());
+        cc.load("pass ..\n  ");
+        let mut lexer = super::Lexer::new(&cc);
+        let res = lexer.run();
+        assert!(matches!(res, Err((LexerErrorType::DanglingContinuator, _))));
+    }
+
+    #[test]
+    fn test_lexer_error_recovery() {
+        let symbols = vec!['='];
+        let regions = reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'",
+                singleline: true
+            }),
+            reg!(comment as "Comment" => {
+                begin: "/*",
+                end: "*/"
+            })
+        ];
+        let expected = vec![
+            ("a".to_string(), (1, 1)),
+            ("=".to_string(), (1, 3)),
+            ("'text".to_string(), (1, 5)),
+            ("b".to_string(), (2, 1)),
+            ("=".to_string(), (2, 3)),
+            ("'ok'".to_string(), (2, 5)),
+            ("/* rest".to_string(), (3, 1))
+        ];
+        let rules = Rules::new(symbols, vec![], regions);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("a = 'text\nb = 'ok'\n/* rest");
+        let mut lexer = super::Lexer::new(&cc);
+        let errors = lexer.run_recovering();
+        let result = lexer.lexem.into_iter().map(|lex| (lex.word, lex.pos)).collect::<Vec<_>>();
+        assert_eq!(expected, result);
+        let errors = errors.into_iter().map(|(kind, info)| (kind, info.data.unwrap())).collect::<Vec<_>>();
+        assert!(matches!(errors.as_slice(), [
+            (LexerErrorType::Singleline, string),
+            (LexerErrorType::Unclosed, comment)
+        ] if string == "String" && comment == "Comment"));
+        // Without recovery the lexer stops at the first error
+        let mut lexer = super::Lexer::new(&cc);
+        assert!(matches!(lexer.run(), Err((LexerErrorType::Singleline, _))));
+    }
+
+    #[test]
+    fn test_lexer_nestable_regions() {
+        let regions = reg![
+            reg!(comment as "Comment" => {
+                begin: "/*",
+                end: "*/",
+                nestable: true
+            })
+        ];
+        let expected = vec![
+            ("/* outer /* inner */ still comment */".to_string(), (1, 1)),
+            ("code".to_string(), (1, 39))
+        ];
+        let rules = Rules::new(vec![], vec![], regions);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("/* outer /* inner */ still comment */ code");
+        let mut lexer = super::Lexer::new(&cc);
+        assert!(lexer.run().is_ok());
+        let result = lexer.lexem.into_iter().map(|lex| (lex.word, lex.pos)).collect::<Vec<_>>();
+        assert_eq!(expected, result);
+        // Unclosed error reports where each level has been opened
+        cc.load("/* a\n  /* b /* c */");
+        let mut lexer = super::Lexer::new(&cc);
+        match lexer.run() {
+            Err((LexerErrorType::UnclosedNested(opened_at), _)) => assert_eq!(opened_at, vec![(1, 1), (2, 3)]),
+            _ => panic!("Expected nested region to be unclosed")
+        }
+        // Delimiters can consist of multibyte characters
+        let regions = reg![
+            reg!(quote as "Quote" => {
+                begin: "«",
+                end: "»",
+                nestable: true
+            })
+        ];
+        let rules = Rules::new(vec![], vec![], regions);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("«a «ż» b» c");
+        let result = cc.tokenize().unwrap().into_iter().map(|lex| (lex.word, lex.pos)).collect::<Vec<_>>();
+        assert_eq!(result, vec![("«a «ż» b»".to_string(), (1, 1)), ("c".to_string(), (1, 11))]);
+    }
+
+    #[test]
+    fn test_lexer_dynamic_delimiters() {
+        let symbols = vec!['='];
+        let regions = reg![
+            reg!(raw as "Raw string" => {
+                begin: "r#\"",
+                end: "\"#",
+                delimiter: Delimiter::Repeat('#')
+            }),
+            reg!(long as "Long string" => {
+                begin: "[=[",
+                end: "]=]",
+                delimiter: Delimiter::Repeat('=')
+            }),
+            reg!(heredoc as "Heredoc" => {
+                begin: "<<",
+                end: "",
+                delimiter: Delimiter::Identifier,
+                strip_indent: true
+            })
+        ];
+        let expected = vec![
+            ("a".to_string(), None),
+            ("=".to_string(), None),
+            ("r##\"x \"# y\"##".to_string(), None),
+            ("b".to_string(), None),
+            ("=".to_string(), None),
+            ("[==[ ]] ]=] ]==]".to_string(), None),
+            ("c".to_string(), None),
+            ("=".to_string(), None),
+            ("<<END\n    one\n      EOF\n    END".to_string(), Some("<<END\none\n  EOF\nEND".to_string())),
+            ("d".to_string(), None)
+        ];
+        let rules = Rules::new(symbols, vec![], regions);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load([
+            "a = r##\"x \"# y\"##",
+            "b = [==[ ]] ]=] ]==]",
+            "c = <<END",
+            "    one",
+            "      EOF",
+            "    END",
+            "d"
+        ].join("\n"));
+        let mut lexer = super::Lexer::new(&cc);
+        assert!(lexer.run().is_ok());
+        let result = lexer.lexem.into_iter().map(|lex| (lex.word, lex.value)).collect::<Vec<_>>();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_lexer_escape_sequences() {
+        let regions = reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'",
+                escapes: Some(EscapeTable::standard())
+            } => [
+                reg!(string_interp as "String interpolation" => {
+                    begin: "{",
+                    end: "}",
+                    tokenize: true
+                } ref global)
+            ]),
+            reg!(raw as "Raw string" => {
+                begin: "`",
+                end: "`"
+            })
+        ];
+        let expected = vec![
+            ("'a\\tb\\'".to_string(), Some("'a\tb'".to_string())),
+            ("{".to_string(), None),
+            ("x".to_string(), None),
+            ("}".to_string(), None),
+            ("\\x41'".to_string(), Some("A'".to_string())),
+            ("`a\\tb`".to_string(), None),
+            ("'plain'".to_string(), None),
+            ("'\\q'".to_string(), None)
+        ];
+        let rules = Rules::new(vec![], vec![], regions);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("'a\\tb\\'{x}\\x41' `a\\tb`\n'plain' '\\q'");
+        let mut lexer = super::Lexer::new(&cc);
+        let errors = lexer.run_recovering();
+        let result = lexer.lexem.iter().map(|lex| (lex.word.clone(), lex.value.clone())).collect::<Vec<_>>();
+        assert_eq!(expected, result);
+        match errors.as_slice() {
+            [(LexerErrorType::InvalidEscape, info)] => {
+                assert_eq!(info.get_pos_by_code(""), (2, 10));
+                assert_eq!(info.data, Some("\\q".to_string()));
+            }
+            _ => panic!("Expected one invalid escape sequence")
+        }
+        // Without recovery the lexer stops at the invalid escape sequence
+        let mut lexer = super::Lexer::new(&cc);
+        assert!(matches!(lexer.run(), Err((LexerErrorType::InvalidEscape, _))));
+    }
+
+    #[test]
+    fn test_lexer_escape_policies() {
+        let regions = reg![
+            reg!(raw as "Raw string" => {
+                begin: "`",
+                end: "`",
+                escape: EscapePolicy::Disabled
+            }),
+            reg!(caret as "Caret string" => {
+                begin: "\"",
+                end: "\"",
+                escapes: Some(EscapeTable::standard()),
+                escape: EscapePolicy::Symbol('^')
+            }),
+            reg!(sql as "SQL string" => {
+                begin: "'",
+                end: "'",
+                escape: EscapePolicy::Doubled
+            })
+        ];
+        let expected = vec![
+            ("`a\\`".to_string(), None),
+            ("\"a^\"^n\\\"".to_string(), Some("\"a\"\n\\\"".to_string())),
+            ("'It''s'".to_string(), Some("'It's'".to_string())),
+            ("''''".to_string(), Some("'''".to_string()))
+        ];
+        let rules = Rules::new(vec![], vec![], regions);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("`a\\` \"a^\"^n\\\" 'It''s' ''''");
+        let mut lexer = super::Lexer::new(&cc);
+        lexer.run().unwrap();
+        let result = lexer.lexem.iter().map(|lex| (lex.word.clone(), lex.value.clone())).collect::<Vec<_>>();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_lexer_region_alternatives() {
+        let regions = reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'",
+                alternatives: vec![(String::from("\""), String::from("\""))],
+                escape: EscapePolicy::Doubled
+            }),
+            reg!(comment as "Comment" => {
+                begin: "//",
+                end: "\n",
+                alternative_ends: vec![String::from("\r\n")],
+                end_at_eof: true
+            })
+        ];
+        let expected = vec![
+            ("a".to_string(), None),
+            ("'It''s'".to_string(), Some("'It's'".to_string())),
+            ("\"b\"\"c'\"".to_string(), Some("\"b\"c'\"".to_string())),
+            ("// one\r\n".to_string(), None),
+            ("\n".to_string(), None),
+            ("b".to_string(), None),
+            ("// two".to_string(), None)
+        ];
+        let rules = Rules::new(vec![], vec![], regions);
+        let mut cc: Compiler = Compiler::try_new("TestScript", rules).unwrap();
+        cc.load("a 'It''s' \"b\"\"c'\" // one\r\nb // two");
+        let mut lexer = super::Lexer::new(&cc);
+        lexer.run().unwrap();
+        let result = lexer.lexem.iter().map(|lex| (lex.word.clone(), lex.value.clone())).collect::<Vec<_>>();
+        assert_eq!(expected, result);
+        assert_eq!(lexer.lexem[6].kind, TokenKind::Region(String::from("comment")));
+        // Region that does not end at the end of the code is still reported
+        let rules = Rules::new(vec![], vec![], reg![
+            reg!(comment as "Comment" => {
+                begin: "//",
+                end: "\n"
+            })
+        ]);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("b // two");
+        let mut lexer = super::Lexer::new(&cc);
+        assert!(matches!(lexer.run(), Err((LexerErrorType::Unclosed, _))));
+    }
+
+    #[test]
+    fn test_lexer_region_guard() {
+        // Regular expression begins after an operator, otherwise it's a division
+        let guard = RegionGuard::new("after operator", |previous| match previous {
+            Some(token) => match token.kind {
+                TokenKind::Word => false,
+                TokenKind::Keyword => token.word == "return",
+                _ => token.word != ")"
+            },
+            None => true
+        });
+        let regions = reg![
+            reg!(regex as "Regular expression" => {
+                begin: "/",
+                end: "/",
+                guard: Some(guard)
+            })
+        ];
+        let symbols = vec!['/', '(', ')', '=', ';'];
+        let rules = Rules::new(symbols, vec![], regions)
+            .set_keywords(vec!["return"]);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("/a/; x = a/2/(b) / c; y = (/b+/); return /c/");
+        let mut lexer = super::Lexer::new(&cc);
+        lexer.run().unwrap();
+        let result = lexer.lexem.iter()
+            .filter(|lex| matches!(lex.kind, TokenKind::Region(_)))
+            .map(|lex| lex.word.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec!["/a/", "/b+/", "/c/"]);
+        let divisions = lexer.lexem.iter().filter(|lex| lex.word == "/").count();
+        assert_eq!(divisions, 3);
+    }
+
+    #[test]
+    fn test_lexer_balanced_region() {
+        let symbols = vec!['{', '}', '(', ')', ':', '.'];
+        let regions = reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'"
+            } => [
+                reg!(interp as "Interpolation" => {
+                    begin: "${",
+                    end: "}",
+                    tokenize: true,
+                    balanced: vec![('{', '}'), ('(', ')')]
+                } ref global)
+            ])
+        ];
+        let expected = vec![
+            "'", "$", "{", "{", "a", ":", "1", "}", ".", "a", "}", " and ",
+            "$", "{", "f", "(", ")", "{", "'}'", "}", "}", "!'", "}"
+        ];
+        let rules = Rules::new(symbols, vec![], regions);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("'${ {a: 1}.a } and ${ f() { '}' } }!' }");
+        let mut lexer = super::Lexer::new(&cc);
+        lexer.run().unwrap();
+        let result = lexer.lexem.iter().map(|lex| lex.word.clone()).collect::<Vec<_>>();
+        assert_eq!(expected, result);
+        // Interpolations end only at the brace that balances their brackets
+        let ends = lexer.lexem.iter().enumerate()
+            .filter(|(_, lex)| lex.kind == TokenKind::RegionEnd)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        assert_eq!(ends, vec![10, 20]);
+    }
+
+    #[test]
+    fn test_lexer_indentation_region() {
+        let regions = reg![
+            reg!(block as "Block scalar" => {
+                begin: "|",
+                end: "",
+                delimiter: Delimiter::Indentation
+            })
+        ];
+        let lines = [
+            "config:",
+            "  script: |",
+            "    echo a",
+            "      | indented",
+            "",
+            "    echo b",
+            "  name: |",
+            "  other: 1"
+        ];
+        let rules = Rules::new(vec![':'], vec![], regions);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.use_indents();
+        cc.load(lines.join("\n"));
+        let mut lexer = super::Lexer::new(&cc);
+        lexer.run().unwrap();
+        let result = lexer.lexem.iter()
+            .filter(|lex| matches!(lex.kind, TokenKind::Region(_)))
+            .map(|lex| (lex.word.clone(), lex.value.clone()))
+            .collect::<Vec<_>>();
+        let expected = vec![
+            ("|\n    echo a\n      | indented\n\n    echo b".to_string(), Some("|\necho a\n  | indented\n\necho b".to_string())),
+            ("|".to_string(), None)
+        ];
+        assert_eq!(expected, result);
+        // Lines that follow the regions are still indented as usual
+        let words = lexer.lexem.iter().map(get_word).collect::<Vec<_>>();
+        assert_eq!(words[7..], ["\n  ", "name", ":", "|", "\n  ", "other", ":", "1", "<dedent>"]);
+    }
+
+    #[test]
+    fn test_lexer_tab_width() {
+        let rules = Rules::new(vec![], vec![], reg![]);
+        let mut cc: Compiler = Compiler::new("Testhon", rules);
+        cc.use_indents();
+        cc.set_tab_width(4);
+        cc.mixed_indent = MixedIndentPolicy::Allow;
+        cc.load("a\tb\n\tc\n  \td");
+        let mut lexer = super::Lexer::new(&cc);
+        lexer.run().unwrap();
+        let result = lexer.lexem.iter()
+            .filter(|lex| lex.kind == TokenKind::Word)
+            .map(|lex| (lex.word.as_str(), lex.pos, lex.span.end_pos))
+            .collect::<Vec<_>>();
+        assert_eq!(result, vec![("a", (1, 1), (1, 2)), ("b", (1, 5), (1, 6)), ("c", (2, 5), (2, 6)), ("d", (3, 5), (3, 6))]);
+        // Indentation that mixes tabs and spaces reaches the same tab stop
+        assert_eq!(lexer.lexem.iter().filter(|lex| lex.kind == TokenKind::Dedent).count(), 1);
+        assert!(lexer.warnings.is_empty());
+        // Policy decides what happens with indentation that mixes tabs and spaces
+        cc.mixed_indent = MixedIndentPolicy::Reject;
+        cc.load("a\n\tb\n    c");
+        assert!(matches!(cc.tokenize(), Err((LexerErrorType::MixedIndent, _))));
+        cc.mixed_indent = MixedIndentPolicy::Allow;
+        assert!(cc.tokenize().is_ok());
+        cc.mixed_indent = MixedIndentPolicy::Warn;
+        let mut lexer = super::Lexer::new(&cc);
+        lexer.run().unwrap();
+        assert_eq!(lexer.warnings.len(), 1);
+        assert!(matches!(lexer.warnings[0], (LexerErrorType::MixedIndent, _)));
+    }
+
+    #[test]
+    fn test_lexer_line_endings() {
+        let lines = [
+            "if a:",
+            "    b = 'x'",
+            "  ",
+            "    c",
+            "d"
+        ];
+        let symbols = vec![':', '='];
+        let regions = reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'"
+            })
+        ];
+        let rules = Rules::new(symbols, vec![], regions);
+        let mut cc: Compiler = Compiler::new("Testhon", rules);
+        cc.use_indents();
+        let mut results = vec![];
+        for newline in ["\n", "\r\n"] {
+            let code = lines.join(newline);
+            cc.load(&code);
+            let lexem = cc.tokenize().unwrap();
+            // Carriage returns do not end up in the tokens
+            assert!(lexem.iter().all(|lex| !lex.word.contains('\r')));
+            assert!(lexem.iter().all(|lex| lex.word.starts_with('\n') || lex.is_synthetic() || code[lex.span.start..lex.span.end] == lex.word));
+            results.push(lexem.iter().map(|lex| (lex.word.clone(), lex.pos)).collect::<Vec<_>>());
+        }
+        assert_eq!(results[0], results[1]);
+    }
+
+    #[test]
+    fn test_lexer_line_ending_positions() {
+        let rules = Rules::new(vec![], vec![], reg![]);
+        let mut cc: Compiler = Compiler::new("Testhon", rules);
+        cc.use_indents();
+        cc.load("a\r\nbb\r\nc\r\nd");
+        let lexem = cc.tokenize().unwrap();
+        // Carriage return that precedes a new line does not take any column
+        let result = lexem.iter().map(|lex| (lex.word.as_str(), lex.span.start_pos, lex.span.end_pos)).collect::<Vec<_>>();
+        assert_eq!(result, vec![
+            ("a", (1, 1), (1, 2)),
+            ("\n", (1, 2), (2, 1)),
+            ("bb", (2, 1), (2, 3)),
+            ("\n", (2, 3), (3, 1)),
+            ("c", (3, 1), (3, 2)),
+            ("\n", (3, 2), (4, 1)),
+            ("d", (4, 1), (4, 2))
+        ]);
+        assert!(lexem.iter().all(|lex| lex.span.utf16_col == Some(lex.span.start_pos.1)));
+        // Carriage return that is not followed by a new line takes a column
+        cc.load("a\rb");
+        assert_eq!(cc.tokenize().unwrap()[0].span.end_pos, (1, 4));
+    }
+
+    #[test]
+    fn test_lexer_bom_and_shebang() {
+        let code = "\u{FEFF}#!/usr/bin/env amber\nlet a";
+        let rules = Rules::new(vec![], vec![], reg![]).allow_shebang();
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load(code);
+        let mut lexer = super::Lexer::new(&cc);
+        lexer.run().unwrap();
+        assert!(lexer.bom);
+        assert_eq!(lexer.shebang, Some(String::from("#!/usr/bin/env amber")));
+        let result = lexer.lexem.iter().map(|lex| (lex.word.as_str(), lex.pos, lex.span.start)).collect::<Vec<_>>();
+        assert_eq!(result, vec![("let", (2, 1), 24), ("a", (2, 5), 28)]);
+        // Byte order mark does not take any column
+        cc.load("\u{FEFF}let");
+        assert_eq!(cc.tokenize().unwrap()[0].pos, (1, 1));
+        // Shebang is a part of the trivia
+        // Compiler reports what has been skipped
+        cc.load(code);
+        let (_, preamble) = cc.tokenize_with_preamble().unwrap();
+        assert_eq!(preamble, Preamble { bom: true, shebang: Some(String::from("#!/usr/bin/env amber")) });
+        cc.load("let");
+        assert_eq!(cc.tokenize_with_preamble().unwrap().1, Preamble::default());
+        cc.load(code);
+        cc.preserve_trivia();
+        let lexem = cc.tokenize().unwrap();
+        assert_eq!(lexem.iter().map(|lex| lex.to_source()).collect::<String>(), code);
+    }
+
+    #[test]
+    fn test_lexer_invalid_characters() {
+        let regions = reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'"
+            })
+        ];
+        let rules = Rules::new(vec![';'], vec![], regions)
+            .set_allowed_chars(vec![CharClass::Ascii, CharClass::Alphabetic]);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        // Regions can contain any character
+        cc.load("zażółć; '；'");
+        assert!(cc.tokenize().is_ok());
+        cc.load("a；b\n'\u{200B}' c\u{200B}d");
+        match cc.tokenize() {
+            Err((LexerErrorType::InvalidCharacter(letter), info)) => {
+                assert_eq!(letter, '；');
+                assert!(matches!(info.position, Position::Pos(1, 2)));
+                assert_eq!(info.data, Some(String::from("U+FF1B")));
+            }
+            _ => panic!("Expected an invalid character")
+        }
+        let (lexem, errors) = cc.tokenize_recovering();
+        let letters = errors.iter().map(|(kind, _)| match kind {
+            LexerErrorType::InvalidCharacter(letter) => *letter,
+            _ => panic!("Expected an invalid character")
+        }).collect::<Vec<_>>();
+        assert_eq!(letters, vec!['；', '\u{200B}']);
+        let words = lexem.iter().map(|token| token.word.as_str()).collect::<Vec<_>>();
+        assert_eq!(words, vec!["a；b", "'\u{200B}'", "c\u{200B}d"]);
+        // Tokens of custom matchers are checked as well
+        let rules = Rules::new(vec![], vec![], reg![])
+            .set_allowed_chars(vec![CharClass::Ascii, CharClass::Alphabetic])
+            .add_matcher(TokenMatcher::pattern("sigil", "\\$[^ ]+"));
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("a $b\u{200B}c d");
+        let (lexem, errors) = cc.tokenize_recovering();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].0, LexerErrorType::InvalidCharacter('\u{200B}')));
+        assert!(matches!(errors[0].1.position, Position::Pos(1, 5)));
+        let words = lexem.iter().map(|token| token.word.as_str()).collect::<Vec<_>>();
+        assert_eq!(words, vec!["a", "$b\u{200B}c", "d"]);
+    }
+
+    #[test]
+    fn test_lexer_dangling_reference() {
+        let regions = reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'"
+            } => [
+                reg!(string_interp as "String interpolation" => {
+                    begin: "{",
+                    end: "}",
+                    tokenize: true
+                } ref missing)
+            ])
+        ];
+        let rules = Rules::new(vec!['+'], vec![], regions);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("a + 'b {c}' + d");
+        match cc.tokenize() {
+            Err((LexerErrorType::DanglingReference(reference), info)) => {
+                assert_eq!(reference, "missing");
+                assert!(matches!(info.position, Position::Pos(1, 8)));
+                assert_eq!(info.data, Some(String::from("String interpolation")));
+            }
+            _ => panic!("Expected a dangling reference")
+        }
+        // Region without the referenced interpolations is lexed until its end
+        let (lexem, errors) = cc.tokenize_recovering();
+        assert_eq!(errors.len(), 1);
+        let words = lexem.iter().map(|lex| lex.word.as_str()).collect::<Vec<_>>();
+        assert_eq!(words, vec!["a", "+", "'b ", "{", "c", "}", "'", "+", "d"]);
+    }
+
+    #[test]
+    fn test_lexer_lossless_mode() {
+        let symbols = vec!['=', '(', ')'];
+        let regions = reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'"
+            }),
+            reg!(comment as "Comment" => {
+                begin: "//",
+                end: "\n",
+                trivia: true
+            }),
+            reg!(block_comment as "Block comment" => {
+                begin: "/*",
+                end: "*/",
+                trivia: true
+            })
+        ];
+        let code = "let a = 'x' // note\n  foo(a)  /* c */\n";
+        let expected = vec![
+            ("", "let", " "),
+            ("", "a", " "),
+            ("", "=", " "),
+            ("", "'x'", " // note"),
+            ("", "\n", ""),
+            ("  ", "foo", ""),
+            ("", "(", ""),
+            ("", "a", ""),
+            ("", ")", "  /* c */\n")
+        ];
+        let rules = Rules::new(symbols, vec![], regions);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.preserve_trivia();
+        cc.load(code);
+        let lexem = cc.tokenize().unwrap();
+        let result = lexem.iter()
+            .filter(|lex| !lex.trivia)
+            .map(|lex| (lex.leading_trivia.as_str(), lex.word.as_str(), lex.trailing_trivia.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(expected, result);
+        assert_eq!(lexem.iter().map(|lex| lex.to_source()).collect::<String>(), code);
+        // Trivia regions are kept in the token stream as well
+        let trivia = lexem.iter().filter(|lex| lex.trivia).map(|lex| lex.word.as_str()).collect::<Vec<_>>();
+        assert_eq!(trivia, vec!["// note\n", "/* c */"]);
+        // Code that consists only of trivia is kept as well
+        cc.load("  /* only */ ");
+        let lexem = cc.tokenize().unwrap();
+        assert_eq!(lexem.iter().map(|lex| lex.to_source()).collect::<String>(), "  /* only */ ");
+    }
+
+    #[test]
+    fn test_lexer_trivia_regions() {
+        let symbols = vec!['+'];
+        let regions = reg![
+            reg!(comment as "Comment" => {
+                begin: "/*",
+                end: "*/",
+                trivia: true
+            })
+        ];
+        let rules = Rules::new(symbols, vec![], regions);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("a /* one */ + /* two */ /* three */ b /* four */");
+        let lexem = cc.tokenize().unwrap();
+        let result = lexem.iter().map(|lex| (lex.word.as_str(), lex.trivia)).collect::<Vec<_>>();
+        assert_eq!(result, vec![
+            ("a", false),
+            ("/* one */", true),
+            ("+", false),
+            ("/* two */", true),
+            ("/* three */", true),
+            ("b", false),
+            ("/* four */", true)
+        ]);
+        // Parser does not see the trivia
+        let mut meta = DefaultMetadata::new(lexem, None, None);
+        let mut words = vec![];
+        while let Some(token) = meta.get_current_token() {
+            words.push(token.word);
+            meta.increment_index();
+        }
+        assert_eq!(words, vec!["a", "+", "b"]);
+    }
+
+    #[test]
+    fn test_lexer_trivia_lines() {
+        let symbols = vec![':'];
+        let regions = reg![
+            reg!(comment as "Comment" => {
+                begin: "#",
+                end: "\n",
+                trivia: true
+            }),
+            reg!(block_comment as "Block comment" => {
+                begin: "/*",
+                end: "*/",
+                trivia: true
+            })
+        ];
+        let rules = Rules::new(symbols, vec![], regions);
+        let mut cc: Compiler = Compiler::new("Testhon", rules);
+        cc.use_indents();
+        let mut lex = |code: &str| {
+            cc.load(code);
+            cc.tokenize().unwrap().iter().map(get_word).collect::<Vec<_>>()
+        };
+        // Comment at the beginning of the line does not close the block
+        assert_eq!(lex("a:\n    b\n# c\n    d\ne\n"), vec![
+            "a", ":", "\n    ", "<indent>", "b", "\n", "# c\n", "\n    ", "d", "\n", "<dedent>", "e", "\n"
+        ]);
+        // Comment that is indented more does not open a block
+        assert_eq!(lex("a:\n    b\n        # c\n\n    d\n"), vec![
+            "a", ":", "\n    ", "<indent>", "b", "\n        ", "# c\n", "\n", "\n    ", "d", "\n", "<dedent>"
+        ]);
+        // Line that holds code after the comment is measured
+        assert_eq!(lex("a:\n    b\n/* c */ d\n"), vec![
+            "a", ":", "\n    ", "<indent>", "b", "\n", "/* c */", "<dedent>", "d", "\n"
+        ]);
+    }
+
+    #[test]
+    fn test_lexer_lossless_synthetic_tokens() {
+        let symbols = vec![':', '+'];
+        let regions = reg![
+            reg!(comment as "Comment" => {
+                begin: "#",
+                end: "\n",
+                trivia: true
+            })
+        ];
+        let code = "if a:\n\tb + # add\n\t\tc\n\n# end\nd";
+        let rules = Rules::new(symbols, vec![], regions);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.separator_mode = SeparatorMode::SemiAutomatic(String::from(";"));
+        cc.use_indents();
+        cc.preserve_trivia();
+        cc.load(code);
+        let lexem = cc.tokenize().unwrap();
+        assert_eq!(lexem.iter().map(|lex| lex.to_source()).collect::<String>(), code);
+        assert!(lexem.iter().any(|lex| lex.word == ";" && lex.is_synthetic()));
+        assert!(lexem.iter().filter(|lex| lex.word.contains('#')).all(|lex| lex.trivia));
+    }
+
+    #[test]
+    fn test_lexer_incremental() {
+        let symbols = vec!['(', ')', '+', '=', ':', '{', '}'];
+        let regions = reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'"
+            } => [
+                reg!(string_interp as "String interpolation" => {
+                    begin: "{",
+                    end: "}",
+                    tokenize: true
+                } ref global)
+            ]),
+            reg!(comment as "Comment" => {
+                begin: "#",
+                end: "\n",
+                trivia: true
+            })
+        ];
+        let code = "let a = 'x {b + c} y' # note\nif a:\n    f(a +\n      b)\n    'ż' + g\nh = 1\n";
+        let edits = [
+            TextEdit::new(0..0, "x "),
+            TextEdit::new(8..9, "'"),
+            TextEdit::new(14..15, "}"),
+            TextEdit::new(23..28, "#"),
+            TextEdit::new(34..34, "\n"),
+            TextEdit::new(45..48, ""),
+            TextEdit::new(59..61, "q\n  "),
+            TextEdit::new(70..70, " + 2"),
+            TextEdit::new(73..73, "\n\ti = 2")
+        ];
+        let configs = [
+            (SeparatorMode::Manual, ScopingMode::Block, false),
+            (SeparatorMode::SemiAutomatic(String::from(";")), ScopingMode::Indent, false),
+            (SeparatorMode::Automatic(String::from("\\")), ScopingMode::Indent, true)
+        ];
+        let mut restarted = false;
+        for (separator_mode, scoping_mode, lossless) in configs {
+            let rules = Rules::new(symbols.clone(), vec![], regions.clone());
+            let mut cc: Compiler = Compiler::new("TestScript", rules);
+            cc.separator_mode = separator_mode;
+            cc.scoping_mode = scoping_mode;
+            cc.lossless = lossless;
+            for edit in edits.iter() {
+                cc.load(code);
+                let tokens = cc.tokenize_recovering().0;
+                let mut edited = code.to_string();
+                edited.replace_range(edit.range.clone(), &edit.text);
+                let result = cc.tokenize_incremental(&tokens, edit.clone());
+                // The edit is kept only if the edited code has been lexed
+                assert_eq!(cc.code.as_deref(), Some(if result.is_ok() { edited.as_str() } else { code }));
+                cc.load(&edited);
+                let expected = cc.tokenize();
+                assert_eq!(expected.is_ok(), result.is_ok());
+                if let (Ok(expected), Ok(result)) = (expected, result) {
+                    assert_eq!(expected, result);
+                }
+                restarted |= super::Lexer::new(&cc).find_restart_point(&tokens, edit.range.start) > 0;
+            }
+        }
+        assert!(restarted);
+    }
+
+    #[test]
+    fn test_lexer_incremental_random_edits() {
+        let symbols = vec!['(', ')', '+', '=', ':', ';', '{', '}'];
+        let regions = reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'"
+            } => [
+                reg!(string_interp as "String interpolation" => {
+                    begin: "{",
+                    end: "}",
+                    tokenize: true
+                } ref global)
+            ]),
+            reg!(comment as "Comment" => {
+                begin: "#",
+                end: "\n",
+                trivia: true
+            })
+        ];
+        let pieces = ["a", "b", "if", "12", "ż", " ", "  ", "\n", "\n  ", "\n    ", "(", ")", "+", "=", "==", ":", ":=", ";", "'", "{", "}", "#"];
+        let configs = [
+            (SeparatorMode::Manual, ScopingMode::Block, false),
+            (SeparatorMode::SemiAutomatic(String::from(";")), ScopingMode::Block, true),
+            (SeparatorMode::SemiAutomatic(String::from(";")), ScopingMode::Indent, false),
+            (SeparatorMode::Automatic(String::from("\\")), ScopingMode::Indent, true)
+        ];
+        // Simple pseudorandom generator so that the test is deterministic
+        let mut seed: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut random = |max: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % max as u64) as usize
+        };
+        for (separator_mode, scoping_mode, lossless) in configs {
+            let rules = Rules::new(symbols.clone(), vec![('=', '='), (':', '=')], regions.clone());
+            let mut cc: Compiler = Compiler::new("TestScript", rules);
+            cc.separator_mode = separator_mode;
+            cc.scoping_mode = scoping_mode;
+            cc.lossless = lossless;
+            for _ in 0..1000 {
+                let code = (0..random(20)).map(|_| pieces[random(pieces.len())]).collect::<String>();
+                cc.load(&code);
+                let Ok(tokens) = cc.tokenize() else {
+                    continue
+                };
+                let boundaries = code.char_indices().map(|(index, _)| index).chain([code.len()]).collect::<Vec<_>>();
+                let (start, end) = (boundaries[random(boundaries.len())], boundaries[random(boundaries.len())]);
+                let text = (0..random(3)).map(|_| pieces[random(pieces.len())]).collect::<String>();
+                let edit = TextEdit::new(start.min(end)..start.max(end), text);
+                let mut edited = code.clone();
+                edited.replace_range(edit.range.clone(), &edit.text);
+                let result = cc.tokenize_incremental(&tokens, edit.clone());
+                cc.load(&edited);
+                let expected = cc.tokenize();
+                assert_eq!(expected.is_ok(), result.is_ok(), "{code:?} {edit:?}");
+                if let (Ok(expected), Ok(result)) = (expected, result) {
+                    assert_eq!(expected, result, "{code:?} {edit:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_lexer_incremental_invalid_edits() {
+        let rules = Rules::new(vec!['+'], vec![], reg![]);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("a + ż");
+        let tokens = cc.tokenize().unwrap();
+        // Past the end of the code, inside of a character and reversed
+        #[allow(clippy::reversed_empty_ranges)]
+        for range in [4..8, 5..5, 3..1] {
+            let result = cc.tokenize_incremental(&tokens, TextEdit::new(range, "b"));
+            assert!(matches!(result, Err((LexerErrorType::InvalidEdit, _))));
+            assert_eq!(cc.code.as_deref(), Some("a + ż"));
+        }
+        // Edit is not applied if the edited code cannot be lexed
+        let rules = Rules::new(vec!['+'], vec![], reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'"
+            })
+        ]);
+        let mut cc: Compiler = Compiler::new("TestScript", rules);
+        cc.load("a + 'b'");
+        let tokens = cc.tokenize().unwrap();
+        assert!(cc.tokenize_incremental(&tokens, TextEdit::new(6..7, "")).is_err());
+        assert_eq!(cc.code.as_deref(), Some("a + 'b'"));
+    }
+
+    #[test]
+    fn test_lexer_streaming() {
+        let symbols = vec!['(', ')', '+', '=', ':'];
+        let regions = reg![
+            reg!(string as "String" => {
+                begin: "'",
+                end: "'"
+            }),
+            reg!(comment as "Comment" => {
+                begin: "#",
+                end: "\n",
+                trivia: true
+            })
+        ];
+        // Code that is long enough to be dropped from the memory while streaming
+        let code = "if a:\n    f('ż' +\n      b) # note\n\nc = 1\n".repeat(1000);
+        let configs = [
+            (SeparatorMode::Manual, ScopingMode::Block, false),
+            (SeparatorMode::SemiAutomatic(String::from(";")), ScopingMode::Indent, true)
+        ];
+        for (separator_mode, scoping_mode, lossless) in configs {
+            let rules = Rules::new(symbols.clone(), vec![], regions.clone());
+            let mut cc: Compiler = Compiler::new("TestScript", rules);
+            cc.separator_mode = separator_mode;
+            cc.scoping_mode = scoping_mode;
+            cc.lossless = lossless;
+            cc.load(&code);
+            let expected = cc.tokenize().unwrap();
+            let input = std::io::Cursor::new(code.clone().into_bytes());
+            let mut lexer = super::Lexer::from_read(&cc, input);
+            let mut result = vec![];
+            let mut memory = 0;
+            while let Some(token) = lexer.next() {
+                result.push(token.unwrap());
+                memory = memory.max(lexer.reader.get_code().len());
+            }
+            assert_eq!(expected, result);
+            assert!(memory < code.len() / 2);
+        }
+        // Input that is not valid UTF-8 cannot be read
+        let rules = Rules::new(symbols, vec![], regions);
+        let cc: Compiler = Compiler::new("TestScript", rules);
+        let input = std::io::Cursor::new(b"a\n\xff b".to_vec());
+        let result = super::Lexer::from_read(&cc, input).collect::<Vec<_>>();
+        assert!(matches!(result.as_slice(), [Ok(_), Err((LexerErrorType::InputError, _))]));
+    }
+
     #[test]
     fn test_lexer_multiline_regions() {
         let symbols = vec![';', '+', '='];
@@ -4,6 +4,9 @@
 
 mod compound_handler;
 mod region_handler;
+mod separator_handler;
+mod span_handler;
+mod indent_handler;
 mod reader;
 mod lexer;
 pub use lexer::*;
\ No newline at end of file
@@ -1,56 +1,224 @@
 #![allow(dead_code)]
+use std::borrow::Cow;
+use std::io::BufRead;
+use crate::compiling::{ get_next_column, get_text_width };
+
 const BEGINNING: (usize, usize) = (0, 1);
+// Minimal amount of bytes that are read from the input ahead of the current character
+const LOOKAHEAD: usize = 1024;
+// Amount of bytes that have to be released before they are dropped from the memory
+const RELEASE_SIZE: usize = 8192;
 
 pub enum ReadMode {
     History,
     Future
 }
 
+/// Cursor over the source code
+///
+/// Reader keeps the byte offset of the current character next to its index,
+/// so that moving forward and looking at the surrounding characters
+/// never needs to walk the code from the beginning.
+///
+/// The code can be also read from an input line by line. In such case only a window
+/// of the code is kept in memory. The window begins at the `base` byte offset
+/// and reaches at least the end of the line that follows the current character.
 pub struct Reader<'a> {
-    pub code: &'a String,
+    code: Cow<'a, str>,
+    // Byte offset of the first character that is kept in memory
+    base: usize,
+    // Input that the rest of the code is read from
+    input: Option<Box<dyn BufRead>>,
+    // Error that stopped reading the input
+    error: Option<std::io::Error>,
     pub row: usize,
     pub col: usize,
     pub index: usize,
-    pub new_line: bool
+    /// Byte offset of the current character
+    pub offset: usize,
+    pub new_line: bool,
+    /// Amount of columns between tab stops
+    pub tab_width: usize,
+    // Length of the current character in bytes
+    letter_len: usize,
+    // Current character
+    letter: char
 }
 
 impl<'a> Reader<'a> {
-    pub fn new(code: &'a String) -> Self {
+    pub fn new(code: &'a str) -> Self {
         Reader {
-            code,
+            code: Cow::Borrowed(code),
+            base: 0,
+            input: None,
+            error: None,
             row: BEGINNING.0,
             col: BEGINNING.1,
             index: 0,
-            new_line: true
+            offset: 0,
+            new_line: true,
+            tab_width: 1,
+            letter_len: 0,
+            letter: '\0'
+        }
+    }
+
+    /// Create a reader that reads the code from the input as it goes
+    pub fn from_input(input: impl BufRead + 'static) -> Self {
+        Reader {
+            code: Cow::Owned(String::new()),
+            input: Some(Box::new(input)),
+            ..Reader::new("")
+        }
+    }
+
+    // Reads lines from the input until enough code is ahead of the current character
+    fn fill(&mut self) {
+        while self.base + self.code.len() < self.offset + LOOKAHEAD {
+            let Some(input) = self.input.as_mut() else {
+                return
+            };
+            let mut line = String::new();
+            match input.read_line(&mut line) {
+                Ok(0) => self.input = None,
+                Ok(_) => self.code.to_mut().push_str(&line),
+                Err(error) => {
+                    self.error = Some(error);
+                    self.input = None;
+                }
+            }
+        }
+    }
+
+    /// Allow dropping the code that precedes the line before the one with given byte offset
+    ///
+    /// The code is dropped only if it has been read from the input.
+    pub fn release(&mut self, offset: usize) {
+        if let Cow::Borrowed(_) = self.code {
+            return
         }
+        let before = &self.code[..offset - self.base];
+        if before.len() < RELEASE_SIZE {
+            return
+        }
+        // Keep the previous line for the delimiters that span multiple lines
+        let line_start = before.rfind('\n').unwrap_or(0);
+        let size = before[..line_start].rfind('\n').map_or(0, |offset| offset + 1);
+        if size > 0 {
+            self.code.to_mut().drain(..size);
+            self.base += size;
+        }
+    }
+
+    /// Take the error that stopped reading the input
+    pub fn take_error(&mut self) -> Option<std::io::Error> {
+        self.error.take()
+    }
+
+    /// Get byte offset of the first character that is kept in memory
+    #[inline]
+    pub fn get_base(&self) -> usize {
+        self.base
+    }
+
+    /// Get the code that is kept in memory
+    #[inline]
+    pub fn get_code(&self) -> &str {
+        &self.code
+    }
+
+    /// Get the code that begins at given byte offset
+    #[inline]
+    pub fn get_code_from(&self, offset: usize) -> &str {
+        &self.code[offset - self.base..]
+    }
+
+    /// Get the code that is kept in memory up to given byte offset
+    #[inline]
+    pub fn get_code_until(&self, offset: usize) -> &str {
+        &self.code[..offset - self.base]
+    }
+
+    // Amount of columns that the current line takes before the current character
+    fn get_line_width(&self) -> usize {
+        let code = self.get_code_until(self.offset);
+        get_text_width(&code[code.rfind('\n').map_or(0, |index| index + 1)..], self.tab_width)
+    }
+
+    /// Get the indentation of the line of the current character
+    pub fn get_line_indent(&self) -> &str {
+        let code = self.get_code_until(self.offset);
+        let line = &code[code.rfind('\n').map_or(0, |index| index + 1)..];
+        let size = line.len() - line.trim_start_matches([' ', '\t']).len();
+        &line[..size]
+    }
+
+    /// Get the code between given byte offsets if it's kept in memory
+    #[inline]
+    pub fn get_slice(&self, start: usize, end: usize) -> Option<&str> {
+        self.code.get(start.checked_sub(self.base)?..end.checked_sub(self.base)?)
     }
 
     #[inline]
     pub fn next_letter(&mut self) -> Option<char> {
+        let previous = (self.row > 0).then_some(self.letter);
         if self.row > 0 {
             self.index += 1;
-            self.col += 1;
+            self.offset += self.letter_len;
+        }
+        self.fill();
+        let next = self.get_rest().chars().next();
+        // Carriage return that precedes a new line belongs to the line ending and takes no column
+        if let Some(previous) = previous.filter(|previous| *previous != '\r' || next != Some('\n')) {
+            self.col = get_next_column(self.col, previous, self.tab_width);
         }
         if self.new_line {
             self.new_line = false;
             self.row += 1;
             self.col = BEGINNING.1;
         }
-        match self.code.chars().nth(self.index) {
+        match next {
             Some(letter) => {
                 self.new_line = letter == '\n';
+                self.letter = letter;
+                self.letter_len = letter.len_utf8();
                 Some(letter)
             }
-            None => None
+            None => {
+                self.letter_len = 0;
+                None
+            }
         }
     }
 
+    /// Move the reader right after the character that ends at given byte offset
+    ///
+    /// The character has to be at given index and end at given position (row, column).
+    pub fn seek(&mut self, offset: usize, index: usize, (row, col): (usize, usize)) {
+        let Some(letter) = self.get_code_until(offset).chars().next_back() else {
+            return
+        };
+        self.letter_len = letter.len_utf8();
+        self.offset = offset - self.letter_len;
+        self.index = index - 1;
+        self.new_line = letter == '\n';
+        self.letter = letter;
+        // Position right after the new line character is at the beginning of the next row
+        (self.row, self.col) = if self.new_line { (row - 1, col) } else { (row, self.get_line_width() + 1) };
+    }
+
     /// Return current index of the string
     #[inline]
     pub fn get_index(&self) -> usize {
         self.index
     }
 
+    /// Return byte offset of the current character
+    #[inline]
+    pub fn get_offset(&self) -> usize {
+        self.offset
+    }
+
     /// Return current position in code
     #[inline]
     pub fn get_position(&self) -> (usize, usize) {
@@ -63,32 +231,44 @@ impl<'a> Reader<'a> {
         (self.row, self.col - word.chars().count())
     }
 
-    /// Workaround for UTF-8 symbols
+    // Byte offset right after the current character
     #[inline]
-    fn get_slice(&self, begin: usize, end: usize) -> String {
-        self.code.chars().skip(begin).take(end - begin).collect::<String>()
+    fn get_end_offset(&self) -> usize {
+        match self.get_rest().chars().next() {
+            Some(letter) => self.offset + letter.len_utf8(),
+            None => self.offset
+        }
     }
 
     /// Get last n characters that were processed in correct order
     /// This function includes currently processed character
     #[inline]
     pub fn get_history(&self, n: usize) -> Option<String> {
-        let offset = self.index + 1;
-        // Handle arithmetic overflow
-        let begin = if offset >= n { offset - n } else { return None };
-        let end = offset;
-        Some(self.get_slice(begin, end))
+        if n == 0 {
+            return Some(String::new())
+        }
+        let end = self.get_end_offset();
+        // Walk back only as far as the window reaches
+        let code = self.get_code_until(end);
+        let (begin, _) = code.char_indices().rev().nth(n - 1)?;
+        Some(code[begin..].to_string())
     }
 
     /// Show next character that is going to be consumed
     #[inline]
     pub fn peek(&self) -> Option<char> {
-        // Amount required to peek one item in the future
-        let one_forward = 2;
-        match self.get_future(one_forward) {
-            Some(value) => value.chars().nth(one_forward - 1),
-            None => None
-        }
+        let mut letters = self.get_rest().chars();
+        letters.next()?;
+        letters.next()
+    }
+
+    /// Check if the current character is followed by a new line
+    /// (carriage return before the new line belongs to the line ending)
+    #[inline]
+    pub fn is_followed_by_newline(&self) -> bool {
+        let rest = self.get_rest();
+        let size = rest.chars().next().map_or(0, char::len_utf8);
+        rest[size..].starts_with('\n') || rest[size..].starts_with("\r\n")
     }
 
     /// Show next character that is going to be consumed depending on the mode
@@ -104,11 +284,21 @@ impl<'a> Reader<'a> {
     /// This function includes currently processed character
     #[inline]
     pub fn get_future(&self, n: usize) -> Option<String> {
-        let begin = self.index;
-        // Handle arithmetic overflow
-        let end = if self.index + n <= self.code.len() { self.index + n } else { return None };
-        // Find the next UTF-8 character boundary for the beginning of the slice
-        Some(self.get_slice(begin, end))
+        let rest = self.get_rest();
+        // Walk forward only as far as the window reaches
+        let end = match rest.char_indices().nth(n) {
+            Some((end, _)) => end,
+            None if rest.chars().count() == n => rest.len(),
+            None => return None
+        };
+        Some(rest[..end].to_string())
+    }
+
+    /// Get the code that has not been processed yet
+    /// This function includes currently processed character
+    #[inline]
+    pub fn get_rest(&self) -> &str {
+        self.get_code_from(self.offset)
     }
 }
 
@@ -196,4 +386,27 @@ mod test {
         assert_eq!(expected, result_history);
         assert_eq!(expected, result_future);
     }
+
+    #[test]
+    fn unicode_history_and_future() {
+        let code = String::from("zażółć");
+        let mut reader = super::Reader::new(&code);
+        let mut result = vec![];
+        // Simulate lexing
+        while let Some(letter) = reader.next() {
+            let history = reader.get_history(2).unwrap_or_default();
+            let future = reader.get_future(2).unwrap_or_default();
+            result.push((letter, reader.get_index(), reader.get_offset(), history, future));
+        }
+        let expected = vec![
+            ('z', 0, 0, String::new(), String::from("za")),
+            ('a', 1, 1, String::from("za"), String::from("aż")),
+            ('ż', 2, 2, String::from("aż"), String::from("żó")),
+            ('ó', 3, 4, String::from("żó"), String::from("ół")),
+            ('ł', 4, 6, String::from("ół"), String::from("łć")),
+            ('ć', 5, 8, String::from("łć"), String::new())
+        ];
+        assert_eq!(expected, result);
+        assert_eq!(reader.peek(), None);
+    }
 }
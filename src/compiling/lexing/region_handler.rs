@@ -1,4 +1,5 @@
-use crate::compiling_rules::{Region, Rules, RegionMap};
+use crate::compiling_rules::{Region, Rules, RegionMap, Delimiter, EscapePolicy};
+use crate::compiling::{ Token, get_text_width };
 use super::reader::Reader;
 use super::reader::ReadMode;
 
@@ -11,14 +12,37 @@ pub enum RegionReaction {
 
 pub struct RegionHandler {
     region_stack: Vec<Region>,
-    region_map: RegionMap
+    region_map: RegionMap,
+    closed_region: Option<Region>,
+    // Positions where the regions on the stack have been opened
+    // (nestable regions store a position for each level)
+    opened_at: Vec<Vec<(usize, usize)>>,
+    // Depth of the balanced brackets that are open in each region on the stack
+    depth: Vec<usize>,
+    // Indentation of the line where each region on the stack has been opened
+    // (regions that end with indentation end once the code gets back to it)
+    line_indent: Vec<String>,
+    // Amount of letters of the dynamic delimiter that are left to be read
+    skip: usize,
+    // Amount of letters of the fixed delimiter that are left to be read
+    // (they are read inside of the region, but they are not its brackets)
+    bracket_skip: usize,
+    // Reference of the region that has just been opened, but does not exist
+    dangling_reference: Option<String>
 }
 
 impl RegionHandler {
     pub fn new(rules: &Rules) -> Self {
         RegionHandler {
             region_stack: vec![rules.region_tree.clone()],
-            region_map: rules.region_tree.clone().generate_region_map()
+            region_map: rules.region_tree.clone().generate_region_map(),
+            closed_region: None,
+            opened_at: vec![vec![]],
+            depth: vec![0],
+            line_indent: vec![String::new()],
+            skip: 0,
+            bracket_skip: 0,
+            dangling_reference: None
         }
     }
 
@@ -27,26 +51,88 @@ impl RegionHandler {
         self.region_stack.last()
     }
 
+    // Region that has been closed most recently
+    #[inline]
+    pub fn get_closed_region(&self) -> Option<&Region> {
+        self.closed_region.as_ref()
+    }
+
+    // Forcefully closes current region
+    // (the global region cannot be closed)
+    #[inline]
+    pub fn close_region(&mut self) -> Option<Region> {
+        if self.region_stack.len() > 1 {
+            self.opened_at.pop();
+            self.depth.pop();
+            self.line_indent.pop();
+            self.closed_region = self.region_stack.pop();
+            self.closed_region.clone()
+        }
+        else { None }
+    }
+
+    // Checks if any region that can begin here has a guard
+    #[inline]
+    pub fn is_guarded(&self) -> bool {
+        self.get_region().is_some_and(|region| region.interp.iter().any(|candidate| candidate.guard.is_some()))
+    }
+
+    // Checks if the region that begins right after current letter is a trivia region
+    pub fn is_followed_by_trivia(&self, reader: &Reader, previous: Option<&Token>) -> bool {
+        let rest = reader.get_rest();
+        let rest = &rest[rest.chars().next().map_or(0, char::len_utf8)..];
+        self.get_region().is_some_and(|region| region.interp.iter()
+            .filter(|candidate| candidate.guard.as_ref().is_none_or(|guard| guard.allows(previous)))
+            .find(|candidate| candidate.match_delimiter(rest).is_some())
+            .is_some_and(|candidate| candidate.trivia))
+    }
+
+    // Region with given id
+    #[inline]
+    pub fn get_region_by_id(&self, id: &str) -> Option<&Region> {
+        self.region_map.get(id)
+    }
+
+    // Reference that could not be found when the current region has been opened
+    #[inline]
+    pub fn take_dangling_reference(&mut self) -> Option<String> {
+        self.dangling_reference.take()
+    }
+
+    // Positions where each nesting level of current region has been opened
+    #[inline]
+    pub fn get_opened_at(&self) -> &[(usize, usize)] {
+        self.opened_at.last().map_or(&[], |opened| opened.as_slice())
+    }
+
     // Error if after code lexing
     // some region was left unclosed
     #[inline]
-    pub fn is_region_closed(&self, reader: &Reader) -> Result<(),((usize, usize), Region)> {
+    pub fn is_region_closed(&self, reader: &Reader) -> Result<(),((usize, usize), String)> {
         if let Some(region) = self.region_stack.last() {
             if !region.allow_left_open {
                 let pos = reader.get_position();
-                return Err((pos, region.clone()));
+                return Err((pos, region.name.clone()));
             }
         }
         Ok(())
     }
 
     // Check where we are in code and open / close some region if matched
-    pub fn handle_region(&mut self, reader: &Reader, is_escaped: bool) -> RegionReaction {
+    // (the previous token is consulted by the guards of the regions)
+    pub fn handle_region(&mut self, reader: &Reader, is_escaped: bool, previous: Option<&Token>) -> RegionReaction {
+        // Dynamic delimiter that opened current region is still being read
+        if self.skip > 0 {
+            self.skip -= 1;
+            return RegionReaction::Pass
+        }
+        let is_begin_read = self.bracket_skip == 0;
+        self.bracket_skip = self.bracket_skip.saturating_sub(1);
         // If we are not in the global scope
         if let Some(region) = self.get_region() {
             for interp_region in region.interp.iter() {
                 // The region that got matched based on current code lexing state
-                if let Some(mut begin_region) = self.match_region_by_begin(reader, is_escaped) {
+                if let Some(mut begin_region) = self.match_region_by_begin(reader, is_escaped, previous) {
                     if begin_region.name == *interp_region.name {
                         // Save the tokenize state here to preserve borrow rules
                         let tokenize = begin_region.tokenize;
@@ -61,29 +147,115 @@ impl RegionHandler {
                                     begin_region.interp = target_region.interp.clone();
                                 },
                                 // If fail then it means that we have invalid reference name
+                                // (region is opened without any interpolations and the lexer reports it)
                                 None => {
-                                    panic!("Could not find region with id '{}'", reference_name);
+                                    self.dangling_reference = Some(reference_name.clone());
                                 }
                             }
                         }
+                        if begin_region.delimiter != Delimiter::Fixed {
+                            self.skip = begin_region.begin.chars().count().saturating_sub(1);
+                        }
+                        else if !begin_region.balanced.is_empty() {
+                            self.bracket_skip = begin_region.begin.chars().count().saturating_sub(1);
+                        }
                         self.region_stack.push(begin_region);
+                        self.opened_at.push(vec![reader.get_position()]);
+                        self.depth.push(0);
+                        self.line_indent.push(reader.get_line_indent().to_string());
                         return RegionReaction::Begin(tokenize);
                     }
                 }
             }
+            let is_nestable = region.nestable;
+            // Region cannot close until all of its balanced brackets are closed
+            let is_balanced = self.depth.last().is_none_or(|depth| *depth == 0);
+            let bracket = self.match_bracket(reader).filter(|_| is_begin_read);
             // Let's check if we can close current region
-            if let Some(end_region) = self.match_region_by_end(reader, is_escaped) {
+            if let Some(end_region) = self.match_region_by_end(reader, is_escaped).filter(|_| is_balanced) {
                 if end_region.name == region.name {
+                    // Doubled delimiter is escaped instead of closing the region
+                    if end_region.escape == EscapePolicy::Doubled && self.is_doubled(&end_region.end, reader) {
+                        self.skip = end_region.end.chars().count();
+                        return RegionReaction::Pass
+                    }
+                    // Nested level closes without closing the region itself
+                    if is_nestable && self.get_opened_at().len() > 1 {
+                        self.opened_at.last_mut().unwrap().pop();
+                        return RegionReaction::Pass
+                    }
                     // Save the tokenize state here to preserve borrow rules
                     let tokenize = end_region.tokenize;
+                    self.opened_at.pop();
+                    self.depth.pop();
+                    self.line_indent.pop();
                     self.region_stack.pop();
+                    // Keep the end that has actually closed the region
+                    self.closed_region = Some(end_region);
                     return RegionReaction::End(tokenize)
                 }
             }
+            if let (Some(depth), Some(is_opening)) = (self.depth.last_mut(), bracket) {
+                *depth = if is_opening { *depth + 1 } else { depth.saturating_sub(1) };
+            }
+            // Nestable region can be opened again inside of itself
+            if is_nestable && self.match_nested_begin(reader, is_escaped) {
+                if let Some(opened_at) = self.opened_at.last_mut() {
+                    opened_at.push(reader.get_position());
+                }
+            }
         }
         RegionReaction::Pass
     }
 
+    // Checks if current letter is one of the balanced brackets of current region
+    // (returns true for an opening bracket and false for a closing one)
+    #[inline]
+    fn match_bracket(&self, reader: &Reader) -> Option<bool> {
+        let region = self.get_region()?;
+        let letter = reader.get_rest().chars().next()?;
+        region.balanced.iter().find_map(|(open, close)| match letter {
+            _ if letter == *open => Some(true),
+            _ if letter == *close => Some(false),
+            _ => None
+        })
+    }
+
+    // Checks if current new line character is followed by a line that ends current indentation region
+    // (blank lines belong to the region and so does everything that is indented more than its beginning)
+    pub fn is_dedented(&self, reader: &Reader) -> bool {
+        if self.get_region().is_none_or(|region| region.delimiter != Delimiter::Indentation) {
+            return false
+        }
+        let line_indent = self.line_indent.last().map_or("", String::as_str);
+        let indent_size = |line: &str| get_text_width(&line[..line.len() - line.trim_start_matches([' ', '\t']).len()], reader.tab_width);
+        reader.get_rest().split('\n')
+            .skip(1)
+            .find(|line| !line.trim().is_empty())
+            .is_some_and(|line| indent_size(line) <= indent_size(line_indent))
+    }
+
+    // Checks if the closing delimiter that ends with current letter is followed by another one
+    #[inline]
+    fn is_doubled(&self, end: &str, reader: &Reader) -> bool {
+        let rest = reader.get_rest();
+        let size = rest.chars().next().map_or(0, char::len_utf8);
+        !end.is_empty() && rest[size..].starts_with(end)
+    }
+
+    // Checks if current region begins once again
+    #[inline]
+    fn match_nested_begin(&self, reader: &Reader, is_escaped: bool) -> bool {
+        let region = self.get_region().unwrap();
+        self.match_region_by(
+            reader,
+            |candidate: &Region| &candidate.begin,
+            std::slice::from_ref(region),
+            ReadMode::Future,
+            is_escaped
+        ).is_some()
+    }
+
     // Matches region by some getter callback
     #[inline]
     fn match_region_by(
@@ -95,36 +267,60 @@ impl RegionHandler {
         is_escaped: bool
     ) -> Option<Region> {
         // Closure that checks if for each given Region is there any that matches current history state
-        let predicate = |candidate: &Region| match reader.get_history_or_future(cb(candidate).len(), &read_mode) {
+        let predicate = |candidate: &Region| match reader.get_history_or_future(cb(candidate).chars().count(), &read_mode) {
             Some(code_chunk) => !is_escaped && &code_chunk == cb(candidate),
             None => false
         };
         self.get_region_by(predicate, candidates)
     }
 
+    // Returns the region with the actual delimiters that it has been opened with
     #[inline]
-    fn match_region_by_begin(&self, reader: &Reader, is_escaped: bool) -> Option<Region> {
+    fn match_region_by_begin(&self, reader: &Reader, is_escaped: bool, previous: Option<&Token>) -> Option<Region> {
+        if is_escaped {
+            return None
+        }
         let region = self.get_region().unwrap();
-        self.match_region_by(
-            reader,
-            |candidate: &Region| &candidate.begin,
-            &region.interp,
-            ReadMode::Future,
-            is_escaped
-        )
+        region.interp.iter()
+            .filter(|candidate| candidate.guard.as_ref().is_none_or(|guard| guard.allows(previous)))
+            .find_map(|candidate| candidate.match_delimiter(reader.get_rest())
+                .map(|(begin, end)| Region { begin, end, ..candidate.clone() }))
+    }
+
+    // Checks if the heredoc ends with current letter
+    // (identifier has to be the only thing in its line)
+    fn match_identifier_end(&self, region: &Region, reader: &Reader) -> bool {
+        let size = region.end.chars().count();
+        if reader.get_history(size).as_ref() != Some(&region.end) || !(reader.peek().is_none() || reader.is_followed_by_newline()) {
+            return false
+        }
+        let mut offset = size + 1;
+        while let Some(history) = reader.get_history(offset) {
+            match history.chars().next() {
+                Some('\n') => return true,
+                Some(' ' | '\t') if region.strip_indent => offset += 1,
+                _ => return false
+            }
+        }
+        false
     }
 
     #[inline]
     fn match_region_by_end(&self, reader: &Reader, is_escaped: bool) -> Option<Region> {
         let region = self.get_region().unwrap();
-        if !region.global {
-            self.match_region_by(
-                reader,
-                |candidate: &Region| &candidate.end,
-                &[region.clone()],
-                ReadMode::History,
-                is_escaped
-            )
+        if region.delimiter == Delimiter::Identifier {
+            self.match_identifier_end(region, reader).then(|| region.clone())
+        }
+        else if region.delimiter == Delimiter::Indentation {
+            None
+        }
+        else if !region.global && !is_escaped {
+            // The longest end wins so that "\r\n" is preferred over "\n"
+            std::iter::once(&region.end)
+                .chain(region.alternative_ends.iter())
+                .filter(|end| reader.get_history(end.chars().count()).as_ref() == Some(*end))
+                .max_by_key(|end| end.len())
+                .map(|end| Region { end: end.clone(), ..region.clone() })
         } else { None }
     }
 
@@ -169,13 +365,20 @@ mod test {
         let mut rh = RegionHandler {
             region_stack: vec![region.clone()],
             region_map: region.generate_region_map(),
+            closed_region: None,
+            opened_at: vec![vec![]],
+            depth: vec![0],
+            line_indent: vec![String::new()],
+            skip: 0,
+            bracket_skip: 0,
+            dangling_reference: None
         };
 
         let mut result = vec![];
         let mut is_escaped = false;
         // Simulate matching regions
         while let Some(letter) = reader.next() {
-            if let Some(begin) = rh.match_region_by_begin(&reader, is_escaped) {
+            if let Some(begin) = rh.match_region_by_begin(&reader, is_escaped, None) {
                 rh.region_stack.push(begin.clone());
                 result.push((reader.get_index(), begin.begin));
             }
@@ -213,13 +416,20 @@ mod test {
         let mut reader = Reader::new(&code);
         let mut rh = RegionHandler {
             region_stack: vec![region.clone()],
-            region_map: region.generate_region_map()
+            region_map: region.generate_region_map(),
+            closed_region: None,
+            opened_at: vec![vec![]],
+            depth: vec![0],
+            line_indent: vec![String::new()],
+            skip: 0,
+            bracket_skip: 0,
+            dangling_reference: None
         };
         let mut result = vec![];
         let mut is_escaped = false;
         // Simulate matching regions
         while let Some(letter) = reader.next() {
-            let region_mutated = rh.handle_region(&reader, is_escaped);
+            let region_mutated = rh.handle_region(&reader, is_escaped, None);
             if let RegionReaction::Begin(_) | RegionReaction::End(_) = region_mutated {
                 result.push(reader.get_index());
             }
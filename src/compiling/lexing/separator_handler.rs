@@ -0,0 +1,264 @@
+use crate::compiling::{ Compiler, Token, TokenKind, SeparatorMode };
+
+// Brackets that make newlines insignificant until they get closed
+const OPEN_BRACKETS: [&str; 2] = ["(", "["];
+// Brackets of blocks in which newlines are significant again
+const OPEN_BLOCKS: [&str; 1] = ["{"];
+const CLOSE_BRACKETS: [&str; 3] = [")", "]", "}"];
+const CLOSE_BLOCK: &str = "}";
+// Operators that are not binary but still need an operand that follows them
+const PREFIX_OPERATORS: [&str; 4] = ["+", "-", "!", "~"];
+
+/// Handles automatic separator insertion
+///
+/// Newline tokens are held back until the next token is known.
+/// Then the newline is either replaced with the separator or dropped.
+pub struct SeparatorHandler {
+    separator: Option<String>,
+    binary_operators: Vec<String>,
+    continuation_keywords: Vec<String>,
+    brackets: Vec<bool>,
+    pending: Option<Token>,
+    previous: Option<Token>
+}
+
+impl SeparatorHandler {
+    pub fn new(cc: &Compiler) -> Self {
+        let separator = match &cc.separator_mode {
+            SeparatorMode::SemiAutomatic(separator) => Some(separator.clone()),
+            _ => None
+        };
+        SeparatorHandler {
+            separator,
+            binary_operators: cc.binary_operators.clone(),
+            continuation_keywords: cc.continuation_keywords.clone(),
+            brackets: vec![],
+            pending: None,
+            previous: None
+        }
+    }
+
+    // Checks if the token is made out of symbols
+    fn is_operator(&self, token: &Token) -> bool {
+        matches!(token.kind, TokenKind::Symbol | TokenKind::Compound)
+    }
+
+    // Checks if newlines are insignificant because we are inside of brackets
+    fn is_in_brackets(&self) -> bool {
+        self.brackets.last().copied().unwrap_or(false)
+    }
+
+    // Checks if the token is a binary operator
+    fn is_binary_operator(&self, token: &Token) -> bool {
+        self.is_operator(token) && self.binary_operators.contains(&token.word)
+    }
+
+    // Checks if the statement can end after this token.
+    // Operators end it unless they expect an operand that follows them,
+    // so that postfix operators such as `x++` end the statement.
+    fn is_statement_end(&self, token: &Token) -> bool {
+        let word = token.word.as_str();
+        if Some(word) == self.separator.as_deref() || OPEN_BRACKETS.contains(&word) || OPEN_BLOCKS.contains(&word) {
+            return false
+        }
+        let is_prefix_operator = self.is_operator(token) && PREFIX_OPERATORS.contains(&word);
+        !self.is_binary_operator(token) && !is_prefix_operator
+    }
+
+    // Checks if the token continues statement from the previous line.
+    // Only binary operators can do that - a line that begins
+    // with an unary operator such as `!x` or `++i` is a new statement.
+    // Closing brace ends the statement on its own, so no separator is needed before it.
+    fn is_continuation(&self, token: &Token) -> bool {
+        let word = token.word.as_str();
+        if Some(word) == self.separator.as_deref() || (self.is_operator(token) && word == CLOSE_BLOCK) {
+            return true
+        }
+        // Keyword such as `else` continues the statement that ends with a block
+        let is_after_block = self.previous.as_ref().is_some_and(|previous| {
+            self.is_operator(previous) && previous.word == CLOSE_BLOCK
+        });
+        if is_after_block && self.continuation_keywords.iter().any(|keyword| keyword == word) {
+            return true
+        }
+        self.is_binary_operator(token)
+    }
+
+    // Creates a synthetic separator in place of given newline
+    fn create_separator(&self, newline: Token) -> Token {
+        Token {
+            word: self.separator.clone().unwrap_or_default(),
+            kind: TokenKind::Symbol,
+            ..newline
+        }
+    }
+
+    // Keeps track of the brackets and blocks that are currently open
+    fn update_brackets(&mut self, token: &Token) {
+        let word = token.word.as_str();
+        if OPEN_BRACKETS.contains(&word) {
+            self.brackets.push(true);
+        }
+        else if OPEN_BLOCKS.contains(&word) {
+            self.brackets.push(false);
+        }
+        else if CLOSE_BRACKETS.contains(&word) {
+            self.brackets.pop();
+        }
+    }
+
+    /// Restores the state from the tokens that have been lexed
+    ///
+    /// The last token has to be a token that has been passed to the handler (not a newline or a separator).
+    pub fn resume(&mut self, lexem: &[Token]) {
+        for token in lexem.iter() {
+            self.update_brackets(token);
+        }
+        self.previous = lexem.last().cloned();
+    }
+
+    /// Decides whether the newline becomes a separator and pushes tokens to the lexem
+    pub fn handle_token(&mut self, token: Token, lexem: &mut Vec<Token>) {
+        // If we don't insert separators - just pass the token through
+        // (trivia such as comments does not affect the separators either)
+        if self.separator.is_none() || token.trivia {
+            lexem.push(token);
+            return
+        }
+        if token.kind == TokenKind::Newline {
+            // Newlines inside of brackets or after an unfinished
+            // statement do not determine the end of the statement
+            let is_insignificant = self.is_in_brackets() || match &self.previous {
+                Some(previous) => !self.is_statement_end(previous),
+                None => true
+            };
+            if self.pending.is_none() && !is_insignificant {
+                self.pending = Some(token);
+            }
+            return
+        }
+        if let Some(newline) = self.pending.take() {
+            if !self.is_continuation(&token) {
+                let separator = self.create_separator(newline);
+                lexem.push(separator);
+            }
+        }
+        self.update_brackets(&token);
+        self.previous = Some(token.clone());
+        lexem.push(token);
+    }
+
+    /// Inserts the last separator if the code ends without one
+    ///
+    /// The `eof` newline token is used in place of the missing newline at the end of the code
+    pub fn finish(&mut self, eof: Token, lexem: &mut Vec<Token>) {
+        if self.separator.is_none() {
+            return
+        }
+        let newline = self.pending.take().unwrap_or(eof);
+        let is_statement_end = match &self.previous {
+            Some(previous) => self.is_statement_end(previous),
+            None => false
+        };
+        if !self.is_in_brackets() && is_statement_end {
+            let separator = self.create_separator(newline);
+            self.previous = Some(separator.clone());
+            lexem.push(separator);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::reg;
+    use crate::compiling_rules::{ Region, Rules };
+    use crate::compiling::{ Compiler, SeparatorMode, Token, TokenKind };
+    use super::SeparatorHandler;
+
+    fn tokenize(words: &[&str]) -> Vec<String> {
+        let rules = Rules::new(vec![], vec![], reg![]);
+        let mut cc = Compiler::new("JS", rules);
+        cc.separator_mode = SeparatorMode::SemiAutomatic(String::from(";"));
+        let mut sh = SeparatorHandler::new(&cc);
+        let mut lexem = vec![];
+        for word in words.iter() {
+            let kind = match *word {
+                "\n" => TokenKind::Newline,
+                "+" | "-" | "!" | "(" | ")" | "{" | "}" | ";" | "." | "," | ":" | "=" => TokenKind::Symbol,
+                "++" | "--" | "==" => TokenKind::Compound,
+                _ => TokenKind::Word
+            };
+            let token = Token { word: word.to_string(), kind, ..Default::default() };
+            sh.handle_token(token, &mut lexem);
+        }
+        let eof = Token { word: String::from("\n"), kind: TokenKind::Newline, ..Default::default() };
+        sh.finish(eof, &mut lexem);
+        lexem.into_iter().map(|token| token.word).collect()
+    }
+
+    #[test]
+    fn insert_separators() {
+        let result = tokenize(&["a", "\n", "\n", "b", "+", "\n", "c", "\n", ".", "d"]);
+        assert_eq!(result, vec!["a", ";", "b", "+", "c", ".", "d", ";"]);
+    }
+
+    #[test]
+    fn skip_newlines_in_brackets() {
+        let result = tokenize(&["f", "(", "\n", "a", "\n", ")", "\n", "b", ";", "\n"]);
+        assert_eq!(result, vec!["f", "(", "a", ")", ";", "b", ";"]);
+    }
+
+    #[test]
+    fn separate_lines_with_unary_operators() {
+        let result = tokenize(&["a", "\n", "!", "b", "\n", "c", "\n", "-", "d", "\n", "e", "\n", "++", "f"]);
+        assert_eq!(result, vec!["a", ";", "!", "b", ";", "c", ";", "-", "d", ";", "e", ";", "++", "f", ";"]);
+        let result = tokenize(&["a", "\n", "==", "b", "\n", ".", "c"]);
+        assert_eq!(result, vec!["a", "==", "b", ".", "c", ";"]);
+    }
+
+    #[test]
+    fn separate_lines_in_blocks_inside_of_brackets() {
+        let result = tokenize(&["f", "(", "\n", "{", "\n", "a", "\n", "b", "\n", "}", "\n", ")", "\n", "c"]);
+        assert_eq!(result, vec!["f", "(", "{", "a", ";", "b", "}", ")", ";", "c", ";"]);
+    }
+
+    #[test]
+    fn end_statements_with_postfix_operators() {
+        let result = tokenize(&["x", "++", "\n", "y", "\n", "z", "--", "\n", "w", "+", "\n", "v", "!", "\n", "u"]);
+        assert_eq!(result, vec!["x", "++", ";", "y", ";", "z", "--", ";", "w", "+", "v", "!", "u", ";"]);
+    }
+
+    #[test]
+    fn skip_separators_around_closing_braces() {
+        let result = tokenize(&["if", "(", "a", ")", "{", "\n", "b", "\n", "}", "\n", "else", "{", "\n", "c", "\n", "}", "\n", "d"]);
+        assert_eq!(result, vec!["if", "(", "a", ")", "{", "b", "}", "else", "{", "c", "}", ";", "d", ";"]);
+        let result = tokenize(&["x", "=", "{", "\n", "a", ":", "1", ",", "\n", "b", ":", "2", "\n", "}", "\n"]);
+        assert_eq!(result, vec!["x", "=", "{", "a", ":", "1", ",", "b", ":", "2", "}", ";"]);
+        // Keywords continue the statement only after a block
+        let result = tokenize(&["a", "\n", "else"]);
+        assert_eq!(result, vec!["a", ";", "else", ";"]);
+    }
+
+    #[test]
+    fn custom_continuation_keywords() {
+        let rules = Rules::new(vec![], vec![], reg![]);
+        let mut cc = Compiler::new("JS", rules);
+        cc.separator_mode = SeparatorMode::SemiAutomatic(String::from(";"));
+        cc.set_continuation_keywords(vec!["elif"]);
+        let mut sh = SeparatorHandler::new(&cc);
+        let mut lexem = vec![];
+        let tokens = [
+            ("}", TokenKind::Symbol),
+            ("\n", TokenKind::Newline),
+            ("elif", TokenKind::Word),
+            ("}", TokenKind::Symbol),
+            ("\n", TokenKind::Newline),
+            ("else", TokenKind::Word)
+        ];
+        for (word, kind) in tokens {
+            sh.handle_token(Token { word: word.to_string(), kind, ..Default::default() }, &mut lexem);
+        }
+        let words: Vec<String> = lexem.into_iter().map(|token| token.word).collect();
+        assert_eq!(words, vec!["}", "elif", "}", ";", "else"]);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::compiling::{ Span, get_next_column, get_text_width };
+use super::reader::Reader;
+
+/// Maps character indices of tokens to spans
+///
+/// Tokens come in the order of the source code, so the handler
+/// keeps a cursor that only walks the distance between two tokens.
+/// The code is accessed through the reader, since it may keep only a part of it.
+pub struct SpanHandler {
+    index: usize,
+    offset: usize,
+    row: usize,
+    col: usize,
+    utf16_col: usize
+}
+
+impl SpanHandler {
+    pub fn new() -> Self {
+        SpanHandler {
+            index: 0,
+            offset: 0,
+            row: 1,
+            col: 1,
+            utf16_col: 1
+        }
+    }
+
+    /// Get byte offset of the cursor
+    pub fn get_offset(&self) -> usize {
+        self.offset
+    }
+
+    // Moves the cursor by one character forward
+    fn advance(&mut self, reader: &Reader) -> bool {
+        match reader.get_code_from(self.offset).chars().next() {
+            Some(letter) => {
+                self.index += 1;
+                self.offset += letter.len_utf8();
+                if letter == '\n' {
+                    self.row += 1;
+                    self.col = 1;
+                    self.utf16_col = 1;
+                } else {
+                    // Carriage return that precedes a new line belongs to the line ending and takes no column
+                    let is_line_ending = letter == '\r' && reader.get_code_from(self.offset).starts_with('\n');
+                    if !is_line_ending {
+                        self.col = get_next_column(self.col, letter, reader.tab_width);
+                        self.utf16_col += letter.len_utf16();
+                    }
+                }
+                true
+            }
+            None => false
+        }
+    }
+
+    // Moves the cursor to the character with given index
+    fn seek(&mut self, reader: &Reader, index: usize) {
+        if index < self.index {
+            while self.index > index {
+                let (offset, letter) = reader.get_code_until(self.offset).char_indices().next_back().unwrap();
+                self.index -= 1;
+                self.offset = reader.get_base() + offset;
+                if letter == '\n' {
+                    self.row -= 1;
+                }
+            }
+            self.update_columns(reader);
+        }
+        while self.index < index && self.advance(reader) {}
+    }
+
+    // Recomputes the columns from the beginning of the line
+    fn update_columns(&mut self, reader: &Reader) {
+        let code = reader.get_code_until(self.offset);
+        let mut line = &code[code.rfind('\n').map_or(0, |offset| offset + 1)..];
+        if reader.get_code_from(self.offset).starts_with('\n') {
+            line = line.strip_suffix('\r').unwrap_or(line);
+        }
+        self.col = get_text_width(line, reader.tab_width) + 1;
+        self.utf16_col = line.encode_utf16().count() + 1;
+    }
+
+    /// Move the cursor to the character with given index, byte offset and row
+    /// without walking the code from the beginning
+    pub fn resume(&mut self, reader: &Reader, index: usize, offset: usize, row: usize) {
+        self.index = index;
+        self.offset = offset;
+        self.row = row;
+        self.update_columns(reader);
+    }
+
+    /// Get span of the token that begins at given character index and consists of `size` characters
+    pub fn get_span(&mut self, reader: &Reader, start: usize, size: usize) -> Span {
+        self.seek(reader, start);
+        let (start, start_pos, utf16_col) = (self.offset, (self.row, self.col), self.utf16_col);
+        for _ in 0..size {
+            if !self.advance(reader) {
+                break
+            }
+        }
+        Span {
+            start,
+            end: self.offset,
+            start_pos,
+            end_pos: (self.row, self.col),
+            utf16_col: Some(utf16_col)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SpanHandler;
+    use super::Reader;
+
+    #[test]
+    fn span_unicode() {
+        let code = "let 𝄞 = 'żółw'\nend";
+        let reader = Reader::new(code);
+        let mut sh = SpanHandler::new();
+        let clef = sh.get_span(&reader, 4, 1);
+        assert_eq!((clef.start, clef.end), (4, 8));
+        assert_eq!((clef.start_pos, clef.end_pos), ((1, 5), (1, 6)));
+        assert_eq!(clef.utf16_col, Some(5));
+        let string = sh.get_span(&reader, 8, 6);
+        assert_eq!((string.start, string.end), (11, 20));
+        assert_eq!(string.utf16_col, Some(10));
+        let end = sh.get_span(&reader, 15, 3);
+        assert_eq!((end.start, end.end), (21, 24));
+        assert_eq!((end.start_pos, end.utf16_col), ((2, 1), Some(1)));
+        // Go back to a token that has already been passed
+        let equals = sh.get_span(&reader, 6, 1);
+        assert_eq!((equals.start, equals.start_pos, equals.utf16_col), (9, (1, 7), Some(8)));
+    }
+}
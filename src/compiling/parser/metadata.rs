@@ -13,7 +13,9 @@ pub struct DefaultMetadata {
     /// Optionally code of the file
     pub code: Option<String>,
     /// Debug value that is used internally
-    pub indent: Option<usize>
+    pub indent: Option<usize>,
+    /// Amount of columns between tab stops
+    pub tab_width: usize
 }
 
 impl Metadata for DefaultMetadata {
@@ -23,7 +25,8 @@ impl Metadata for DefaultMetadata {
             expr: tokens,
             path,
             code,
-            indent: None
+            indent: None,
+            tab_width: 1
         }
     }
 
@@ -54,6 +57,14 @@ impl Metadata for DefaultMetadata {
     fn get_code(&self) -> Option<&String> {
         self.code.as_ref()
     }
+
+    fn get_tab_width(&self) -> usize {
+        self.tab_width
+    }
+
+    fn set_tab_width(&mut self, tab_width: usize) {
+        self.tab_width = tab_width
+    }
 }
 
 /// Metadata for carrying information through the ASI parsing phases.
@@ -77,19 +88,39 @@ pub trait Metadata {
     fn get_path(&self) -> Option<String>;
     /// Getter for code of the file
     fn get_code(&self) -> Option<&String>;
+    /// Getter for amount of columns between tab stops
+    fn get_tab_width(&self) -> usize {
+        1
+    }
+    /// Optionally store amount of columns between tab stops
+    /// (used by the indentation patterns and when rendering the code)
+    fn set_tab_width(&mut self, _tab_width: usize) {}
     /// Get traceback
     fn get_trace(&self) -> Vec<PositionInfo> {
         vec![]
     }
     /// Optionally set logic of incrementing the index number
+    ///
+    /// Trivia tokens (such as comments) that precede the current token are skipped as well.
     fn increment_index(&mut self) {
-        let index = self.get_index();
+        let mut index = self.get_index();
+        while self.get_token_at(index).is_some_and(|token| token.trivia) {
+            index += 1;
+        }
         self.set_index(index + 1);
     }
     /// Return token under current index
+    ///
+    /// Trivia tokens (such as comments) are skipped and the first token that follows them is returned.
     fn get_current_token(&self) -> Option<Token> {
-        let index = self.get_index();
-        self.get_token_at(index)
+        let mut index = self.get_index();
+        while let Some(token) = self.get_token_at(index) {
+            if !token.trivia {
+                return Some(token)
+            }
+            index += 1;
+        }
+        None
     }
     /// Change current index by given offset
     fn offset_index(&mut self, offset: isize) {
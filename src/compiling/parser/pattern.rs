@@ -1,5 +1,6 @@
 use crate::compiling::failing::position_info::PositionInfo;
 use crate::compiling::failing::failure::Failure;
+use crate::compiling::{ TokenKind, get_text_width };
 use super::{ Metadata, SyntaxModule };
 
 /// Matches one token with given word
@@ -48,6 +49,29 @@ pub fn token_by(meta: &mut impl Metadata, cb: impl Fn(&String) -> bool) -> Resul
     }
 }
 
+/// Matches one token of given kind
+///
+/// If token was matched succesfully - the word it contained is returned.
+/// Otherwise detailed information is returned about where this happened.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # fn compile() -> Result<(), Failure> {
+/// # let meta = &mut DefaultMetadata::new(vec![], None, None);
+/// let text = token_kind(meta, TokenKind::Region("string".to_string()))?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn token_kind(meta: &mut impl Metadata, kind: TokenKind) -> Result<String, Failure> {
+    match meta.get_current_token() {
+        Some(token) => if token.kind == kind {
+            meta.increment_index();
+            Ok(token.word)
+        } else { Err(Failure::Quiet(PositionInfo::from_token(meta, Some(token)))) }
+        None => Err(Failure::Quiet(PositionInfo::at_eof(meta)))
+    }
+}
+
 /// Parses syntax module
 ///
 /// If syntax module was parsed succesfully - nothing is returned.
@@ -83,7 +107,8 @@ pub fn syntax<M: Metadata>(meta: &mut M, module: &mut impl SyntaxModule<M>) -> R
 
 /// Matches indentation
 ///
-/// If indentation was matched succesfully - the amount of spaces is returned.
+/// If indentation was matched succesfully - its width in columns is returned
+/// (tabs take the amount of columns set by the tab width of the metadata).
 /// Otherwise detailed information is returned about where this happened.
 /// # Example
 /// ```
@@ -95,9 +120,9 @@ pub fn syntax<M: Metadata>(meta: &mut M, module: &mut impl SyntaxModule<M>) -> R
 /// # }
 /// ```
 pub fn indent(meta: &mut impl Metadata) -> Result<usize, Failure> {
-    let fun = |word: &String| word.starts_with('\n') && word.get(1..).unwrap().chars().all(|letter| letter == ' ');
+    let fun = |word: &String| word.starts_with('\n') && word.get(1..).unwrap().chars().all(|letter| [' ', '\t'].contains(&letter));
     match token_by(meta, fun) {
-        Ok(word) => Ok(word.get(1..).unwrap().len()),
+        Ok(word) => Ok(get_text_width(word.get(1..).unwrap(), meta.get_tab_width())),
         Err(failure) => Err(failure)
     }
 }
@@ -105,7 +130,7 @@ pub fn indent(meta: &mut impl Metadata) -> Result<usize, Failure> {
 /// Matches indentation with provided size
 ///
 /// If indentation was identified succesfully return the std::cmp::Ordering
-/// depending on whether the amount of spaces detected was smaller, equal or greater.
+/// depending on whether the width of the indentation detected was smaller, equal or greater.
 /// Otherwise detailed information is returned about where this happened.
 /// # Example
 /// ```
@@ -118,11 +143,11 @@ pub fn indent(meta: &mut impl Metadata) -> Result<usize, Failure> {
 /// ```
 pub fn indent_with(meta: &mut impl Metadata, size: usize) -> Result<std::cmp::Ordering, Failure> {
     let index = meta.get_index();
-    let fun = |word: &String| word.starts_with('\n') && word.get(1..).unwrap().chars().all(|letter| letter == ' ');
+    let fun = |word: &String| word.starts_with('\n') && word.get(1..).unwrap().chars().all(|letter| [' ', '\t'].contains(&letter));
     match token_by(meta, fun) {
         Ok(word) => {
-            let spaces = word.chars().count() - 1;
-            Ok(spaces.cmp(&size))
+            let width = get_text_width(word.get(1..).unwrap(), meta.get_tab_width());
+            Ok(width.cmp(&size))
         }
         Err(details) => {
             meta.set_index(index);
@@ -131,25 +156,101 @@ pub fn indent_with(meta: &mut impl Metadata, size: usize) -> Result<std::cmp::Or
     }
 }
 
+/// Matches beginning of an indented block
+///
+/// This token is emitted by lexer in the `ScopingMode::Indent` whenever the indentation grows.
+/// If the block has begun succesfully - nothing is returned.
+/// Otherwise detailed information is returned about where this happened.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # fn compile() -> Result<(), Failure> {
+/// # let meta = &mut DefaultMetadata::new(vec![], None, None);
+/// block_begin(meta)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn block_begin(meta: &mut impl Metadata) -> Result<(), Failure> {
+    token_kind(meta, TokenKind::Indent)?;
+    Ok(())
+}
+
+/// Matches end of an indented block
+///
+/// This token is emitted by lexer in the `ScopingMode::Indent` whenever the indentation shrinks.
+/// If the block has ended succesfully - nothing is returned.
+/// Otherwise detailed information is returned about where this happened.
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// # fn compile() -> Result<(), Failure> {
+/// # let meta = &mut DefaultMetadata::new(vec![], None, None);
+/// block_end(meta)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn block_end(meta: &mut impl Metadata) -> Result<(), Failure> {
+    token_kind(meta, TokenKind::Dedent)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
-    use crate::compiling::{DefaultMetadata, Token};
+    use crate::compiling::{DefaultMetadata, Token, TokenKind};
     use super::*;
 
     #[test]
     fn indent_test() {
-        let expr = vec![Token {word: format!("\n    "), pos: (0, 0), start: 0 }];
+        let expr = vec![Token {word: format!("\n    "), pos: (0, 0), start: 0, ..Default::default() }];
         let mut meta = DefaultMetadata::new(expr, Some(format!("path/to/file")), None);
         let res = indent(&mut meta);
         assert!(res.is_ok());
         assert_eq!(res.unwrap(), 4);
     }
 
+    #[test]
+    fn token_kind_test() {
+        let expr = vec![
+            Token { word: "'+'".to_string(), pos: (0, 0), start: 0, kind: TokenKind::Region("string".to_string()), ..Default::default() },
+            Token { word: "+".to_string(), pos: (0, 0), start: 4, kind: TokenKind::Symbol, ..Default::default() }
+        ];
+        let mut meta = DefaultMetadata::new(expr, Some("path/to/file".to_string()), None);
+        assert!(token_kind(&mut meta, TokenKind::Symbol).is_err());
+        assert_eq!(token_kind(&mut meta, TokenKind::Region("string".to_string())).unwrap(), "'+'");
+        assert_eq!(token_kind(&mut meta, TokenKind::Symbol).unwrap(), "+");
+    }
+
+    #[test]
+    fn block_test() {
+        let expr = vec![
+            Token { pos: (0, 0), start: 0, kind: TokenKind::Indent, ..Default::default() },
+            Token { pos: (0, 0), start: 0, kind: TokenKind::Dedent, ..Default::default() }
+        ];
+        let mut meta = DefaultMetadata::new(expr, Some("path/to/file".to_string()), None);
+        assert!(block_end(&mut meta).is_err());
+        // Indentation levels cannot be matched by their word
+        assert!(token_by(&mut meta, |word| word.starts_with('<')).is_err());
+        assert!(block_begin(&mut meta).is_ok());
+        assert!(block_end(&mut meta).is_ok());
+    }
+
     #[test]
     fn indent_with_test() {
-        let expr = vec![Token { word: format!("\n    "), pos: (0, 0), start: 0 }];
+        let expr = vec![Token { word: format!("\n    "), pos: (0, 0), start: 0, ..Default::default() }];
         let mut meta = DefaultMetadata::new(expr, Some(format!("path/to/file")), None);
         let res = indent_with(&mut meta, 4);
         assert!(res.is_ok());
     }
+
+    #[test]
+    fn indent_tab_test() {
+        let expr = vec![
+            Token { word: "\n\t  ".to_string(), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: "\n\t".to_string(), pos: (0, 0), start: 0, ..Default::default() }
+        ];
+        let mut meta = DefaultMetadata::new(expr, Some("path/to/file".to_string()), None);
+        meta.set_tab_width(4);
+        assert_eq!(indent(&mut meta).unwrap(), 6);
+        assert_eq!(indent_with(&mut meta, 4).unwrap(), std::cmp::Ordering::Equal);
+    }
 }
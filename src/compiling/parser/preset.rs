@@ -1,12 +1,29 @@
 use crate::compiling::failing::position_info::PositionInfo;
 use crate::compiling::failing::failure::Failure;
+use crate::compiling::TokenKind;
 use super::Metadata;
 
 /// Match variable name
 /// 
 /// Matches one token with a word that would be considered as a variable name.
+/// Keywords declared in the `Rules` are not considered to be variable names.
+/// In such case the failure contains description of the keyword in the `data` field.
 /// If desired - one can extend this implementation with other chars.
 pub fn variable(meta: &mut impl Metadata, extend: Vec<char>) -> Result<String, Failure> {
+    if let Some(token) = meta.get_current_token() {
+        if token.kind == TokenKind::Keyword {
+            let description = token.describe();
+            return Err(Failure::Quiet(PositionInfo::from_token(meta, Some(token)).data(description)))
+        }
+    }
+    variable_or_keyword(meta, extend)
+}
+
+/// Match variable name or a keyword
+/// 
+/// Works just like `variable` but also accepts keywords declared in the `Rules`.
+/// This is useful for contextual keywords that can be used as names in some places.
+pub fn variable_or_keyword(meta: &mut impl Metadata, extend: Vec<char>) -> Result<String, Failure> {
     match meta.get_current_token() {
         Some(token) => {
             // This boolean stores false if we are past
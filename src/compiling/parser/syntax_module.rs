@@ -95,7 +95,7 @@ mod test {
     use super::*;
     use crate::compiling::parser::pattern::*;
     use crate::compiling::parser::preset::*;
-    use crate::compiling::{ Token, DefaultMetadata, Metadata };
+    use crate::compiling::{ Token, TokenKind, DefaultMetadata, Metadata };
 
     struct Expression {}
     impl SyntaxModule<DefaultMetadata> for Expression {
@@ -117,14 +117,16 @@ mod test {
             Token {
                 word: format!("let"),
                 pos: (0, 0),
-                start: 0
+                start: 0,
+                ..Default::default()
             }
         ];
         let dataset2 = vec![
             Token {
                 word: format!("tell"),
                 pos: (0, 0),
-                start: 0
+                start: 0,
+                ..Default::default()
             }
         ];
         let path = Some(format!("path/to/file"));
@@ -150,20 +152,29 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_variable_keyword() {
+        let keyword = Token { word: "if".to_string(), kind: TokenKind::Keyword, ..Default::default() };
+        let mut meta = DefaultMetadata::new(vec![keyword], None, None);
+        let result = variable(&mut meta, vec![]);
+        assert_eq!(result.unwrap_err().unwrap_quiet().data, Some("keyword `if`".to_string()));
+        assert_eq!(variable_or_keyword(&mut meta, vec![]).unwrap(), "if");
+    }
+
     #[test]
     fn test_preset_match() {
         let mut exp = Preset {};
         let dataset = vec![
             // Variable
-            Token { word: format!("_text"), pos: (0, 0), start: 0 },
+            Token { word: format!("_text"), pos: (0, 0), start: 0, ..Default::default() },
             // Numeric
-            Token { word: format!("12321"), pos: (0, 0), start: 0 },
+            Token { word: format!("12321"), pos: (0, 0), start: 0, ..Default::default() },
             // Number
-            Token { word: format!("-123.12"), pos: (0, 0), start: 0 },
+            Token { word: format!("-123.12"), pos: (0, 0), start: 0, ..Default::default() },
             // Integer
-            Token { word: format!("-12"), pos: (0, 0), start: 0 },
+            Token { word: format!("-12"), pos: (0, 0), start: 0, ..Default::default() },
             // Float
-            Token { word: format!("-.681"), pos: (0, 0), start: 0 }
+            Token { word: format!("-.681"), pos: (0, 0), start: 0, ..Default::default() }
         ];
         let path = Some(format!("path/to/file"));
         let result = exp.parse(&mut DefaultMetadata::new(dataset, path, None));
@@ -211,42 +222,42 @@ mod test {
         let mut exp = PatternModule {};
         // Everything should pass
         let dataset1 = vec![
-            Token { word: format!("orange"), pos: (0, 0), start: 0 },
-            Token { word: format!("optional"), pos: (0, 0), start: 0 },
-            Token { word: format!("let"), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!(","), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!("end"), pos: (0, 0), start: 0 }
+            Token { word: format!("orange"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("optional"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("let"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("this"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!(","), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("this"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("end"), pos: (0, 0), start: 0, ..Default::default() }
         ];
         // Token should fail
         let dataset2 = vec![
-            Token { word: format!("kiwi"), pos: (0, 0), start: 0 },
-            Token { word: format!("optional"), pos: (0, 0), start: 0 },
-            Token { word: format!("let"), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!(","), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!("end"), pos: (0, 0), start: 0 }
+            Token { word: format!("kiwi"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("optional"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("let"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("this"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!(","), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("this"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("end"), pos: (0, 0), start: 0, ..Default::default() }
         ];
         // Syntax should fail
         let dataset3 = vec![
-            Token { word: format!("orange"), pos: (0, 0), start: 0 },
-            Token { word: format!("tell"), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!(","), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!("end"), pos: (0, 0), start: 0 }
+            Token { word: format!("orange"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("tell"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("this"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!(","), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("this"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("end"), pos: (0, 0), start: 0, ..Default::default() }
         ];
         // Token should fail because of repeat matching (this , this) ,
         let dataset4 = vec![
-            Token { word: format!("orange"), pos: (0, 0), start: 0 },
-            Token { word: format!("tell"), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!(","), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!("this"), pos: (0, 0), start: 0 },
-            Token { word: format!("end"), pos: (0, 0), start: 0 }
+            Token { word: format!("orange"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("tell"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("this"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!(","), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("this"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("this"), pos: (0, 0), start: 0, ..Default::default() },
+            Token { word: format!("end"), pos: (0, 0), start: 0, ..Default::default() }
         ];
         let path = Some(format!("path/to/file"));
         let result1 = exp.parse(&mut DefaultMetadata::new(dataset1, path.clone(), None));
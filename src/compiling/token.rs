@@ -1,5 +1,74 @@
 use std::fmt::{Formatter, Display, Result, Debug};
 
+/// Kind of the token determined by the lexer
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum TokenKind {
+    /// Regular word such as an identifier or a number
+    #[default]
+    Word,
+    /// Word that is reserved by the language
+    Keyword,
+    /// Single character symbol
+    Symbol,
+    /// Symbols merged together
+    Compound,
+    /// Region that is not tokenized (such as a string or a comment) with the id of the region
+    Region(String),
+    /// Symbol that opens a tokenized region (such as an interpolation)
+    RegionBegin,
+    /// Symbol that closes a tokenized region (such as an interpolation)
+    RegionEnd,
+    /// New line character
+    ///
+    /// In the `ScopingMode::Indent` it also contains the indentation of the following line.
+    /// These tokens are kept next to `Indent` and `Dedent` since they separate statements
+    /// that are on the same indentation level and `indent` or `indent_with` measure them.
+    Newline,
+    /// Token with an empty word that opens an indentation level
+    Indent,
+    /// Token with an empty word that closes an indentation level
+    Dedent
+}
+
+/// Location of the token in the source code
+///
+/// Span stores both UTF-8 byte offsets and positions (row, column)
+/// so that tools such as editors can map tokens without reading the source again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    /// Byte offset at which the token begins
+    pub start: usize,
+    /// Byte offset right after the token ends
+    pub end: usize,
+    /// Position at which the token begins (row, column)
+    pub start_pos: (usize, usize),
+    /// Position right after the token ends (row, column)
+    pub end_pos: (usize, usize),
+    /// Column at which the token begins counted in UTF-16 code units (starting from 1)
+    pub utf16_col: Option<usize>
+}
+
+impl Span {
+    /// Length of the span in bytes
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Check if the span does not cover any code
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Create a span that begins with this span and ends where the other one begins
+    pub fn until(&self, other: &Span) -> Span {
+        Span {
+            end: other.start.max(self.start),
+            end_pos: other.start_pos.max(self.start_pos),
+            ..*self
+        }
+    }
+}
+
 /// The building block of the AST
 #[derive(Clone, PartialEq, Eq, Default)]
 pub struct Token {
@@ -9,11 +78,70 @@ pub struct Token {
     pub pos: (usize, usize),
     /// Index of the character in the file that the token starts
     pub start: usize,
+    /// Kind of the token
+    pub kind: TokenKind,
+    /// Location of the token in the source code
+    pub span: Span,
+    /// Processed value of the token (such as a region with stripped indentation)
+    /// if it is different from the word
+    pub value: Option<String>,
+    /// Whitespace, newlines and trivia regions that precede the token (only in lossless mode)
+    pub leading_trivia: String,
+    /// Whitespace and trivia regions that follow the token up to the end of the line (only in lossless mode)
+    pub trailing_trivia: String,
+    /// Determines if the token is a trivia region (such as a comment)
+    /// that is skipped by `Metadata::get_current_token`
+    pub trivia: bool
 }
 
 impl Token {
+    /// Get the processed value of the token or the word if it has not been processed
+    pub fn get_value(&self) -> &str {
+        self.value.as_deref().unwrap_or(&self.word)
+    }
+
+    /// Check if the token has been inserted by the lexer (such as an indentation level or a separator)
+    ///
+    /// In lossless mode synthetic tokens have an empty span and do not carry any trivia.
+    pub fn is_synthetic(&self) -> bool {
+        let is_level = matches!(self.kind, TokenKind::Indent | TokenKind::Dedent);
+        self.span.is_empty() && (is_level || !self.word.is_empty())
+    }
+
+    /// Get the source code of the token together with its trivia
+    ///
+    /// In lossless mode concatenating the source code of all the tokens reproduces the input.
+    /// Trivia regions are already a part of the trivia of the surrounding tokens.
+    pub fn to_source(&self) -> String {
+        if self.is_synthetic() || self.trivia {
+            return String::new()
+        }
+        format!("{}{}{}", self.leading_trivia, self.word, self.trailing_trivia)
+    }
+
+    /// Describe the token in a human-readable form
+    ///
+    /// This can be used in error messages such as "expected identifier, found keyword `if`"
+    pub fn describe(&self) -> String {
+        match &self.kind {
+            TokenKind::Word => format!("`{}`", self.word),
+            TokenKind::Keyword => format!("keyword `{}`", self.word),
+            TokenKind::Symbol | TokenKind::Compound => format!("symbol `{}`", self.word),
+            TokenKind::Region(id) => format!("{id} `{}`", self.word),
+            TokenKind::RegionBegin | TokenKind::RegionEnd => format!("`{}`", self.word),
+            TokenKind::Newline => String::from("new line"),
+            TokenKind::Indent => String::from("indentation"),
+            TokenKind::Dedent => String::from("end of indentation")
+        }
+    }
+
     fn format(&self, formatter: &mut Formatter) -> Result {
-        let word = match self.word.as_str() {
+        let word = match self.kind {
+            TokenKind::Indent => return write!(formatter, "Tok[<indent> {}:{}]", self.pos.0, self.pos.1),
+            TokenKind::Dedent => return write!(formatter, "Tok[<dedent> {}:{}]", self.pos.0, self.pos.1),
+            _ => self.word.as_str()
+        };
+        let word = match word {
             "\n" => String::from("<new_line>"),
             "\t" => String::from("<tab>"),
             " " => String::from("<space>"),
@@ -24,7 +152,7 @@ impl Token {
                 "(" | ")" |
                 ":" | ";"
             ) => format!("<symbol: {} >", sym),
-            _ => self.word.clone()
+            _ => word.to_string()
         };
         write!(formatter, "Tok[{} {}:{}]", word, self.pos.0, self.pos.1)
     }
@@ -49,10 +177,29 @@ mod test {
         let mut token = super::Token {
             word: String::from("keyword"),
             pos: (1, 2),
-            start: 0
+            start: 0,
+            kind: super::TokenKind::Word,
+            span: super::Span::default(),
+            value: None,
+            leading_trivia: String::new(),
+            trailing_trivia: String::new(),
+            trivia: false
         };
         assert_eq!(format!("{}", token), String::from("Tok[keyword 1:2]"));
         token.word = String::from("[");
         assert_eq!(format!("{}", token), String::from("Tok[<symbol: [ > 1:2]"));
+        token.word = String::new();
+        token.kind = super::TokenKind::Dedent;
+        assert_eq!(format!("{}", token), String::from("Tok[<dedent> 1:2]"));
+    }
+
+    #[test]
+    fn describe_token() {
+        let token = super::Token {
+            word: String::from("if"),
+            kind: super::TokenKind::Keyword,
+            ..Default::default()
+        };
+        assert_eq!(token.describe(), "keyword `if`");
     }
 }
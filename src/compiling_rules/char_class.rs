@@ -0,0 +1,79 @@
+/// Class of characters that can appear outside of regions
+///
+/// Symbols and whitespace are always allowed. Every other character
+/// that would become a part of a word has to belong to one of the allowed classes.
+///
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// Rules::new(vec![';'], vec![], reg![])
+///     .set_allowed_chars(vec![CharClass::Ascii, CharClass::Alphabetic]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CharClass {
+    /// Letters of any alphabet
+    Alphabetic,
+    /// Digits of any numeral system
+    Numeric,
+    /// Visible ASCII characters (letters, digits and punctuation)
+    Ascii,
+    /// Given character
+    Char(char),
+    /// Characters of the inclusive range
+    Range(char, char)
+}
+
+impl CharClass {
+    /// Check if the character belongs to this class
+    pub fn contains(&self, letter: char) -> bool {
+        match self {
+            CharClass::Alphabetic => letter.is_alphabetic(),
+            CharClass::Numeric => letter.is_numeric(),
+            CharClass::Ascii => letter.is_ascii_graphic(),
+            CharClass::Char(char) => letter == *char,
+            CharClass::Range(begin, end) => (*begin..=*end).contains(&letter)
+        }
+    }
+}
+
+/// Get the ASCII character that looks like the given one
+///
+/// This is useful for hints such as "did you mean `;`" when
+/// the code contains a lookalike character such as `；`.
+pub fn get_confusable(letter: char) -> Option<char> {
+    match letter {
+        // Fullwidth forms of ASCII characters
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(letter as u32 - 0xFEE0),
+        '\u{037E}' => Some(';'),
+        '\u{00A0}' | '\u{2000}'..='\u{200A}' | '\u{3000}' => Some(' '),
+        '\u{2010}'..='\u{2015}' | '\u{2212}' => Some('-'),
+        '\u{2018}' | '\u{2019}' | '\u{201B}' | '\u{2032}' => Some('\''),
+        '\u{201C}' | '\u{201D}' | '\u{201F}' | '\u{2033}' => Some('"'),
+        '\u{00D7}' => Some('*'),
+        '\u{00F7}' | '\u{2044}' | '\u{2215}' => Some('/'),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ CharClass, get_confusable };
+
+    #[test]
+    fn char_classes() {
+        assert!(CharClass::Alphabetic.contains('ż'));
+        assert!(!CharClass::Ascii.contains('ż'));
+        assert!(CharClass::Ascii.contains('_'));
+        assert!(!CharClass::Ascii.contains('\u{200B}'));
+        assert!(CharClass::Range('a', 'f').contains('c'));
+        assert!(CharClass::Char('$').contains('$'));
+    }
+
+    #[test]
+    fn confusables() {
+        assert_eq!(get_confusable('；'), Some(';'));
+        assert_eq!(get_confusable('Ａ'), Some('A'));
+        assert_eq!(get_confusable('“'), Some('"'));
+        assert_eq!(get_confusable('\u{200B}'), None);
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+/// Determines how the contents of a region can be escaped
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum EscapePolicy {
+    /// Use the escape symbol of the rules
+    #[default]
+    Inherit,
+    /// Use a custom escape symbol
+    Symbol(char),
+    /// Nothing can be escaped (such as in raw strings)
+    Disabled,
+    /// The closing delimiter is escaped by writing it twice (such as `'It''s'` in SQL)
+    Doubled
+}
+
+impl EscapePolicy {
+    /// Get the escape symbol that is used with this policy
+    pub fn get_symbol(&self, default: char) -> Option<char> {
+        match self {
+            EscapePolicy::Inherit => Some(default),
+            EscapePolicy::Symbol(symbol) => Some(*symbol),
+            EscapePolicy::Disabled | EscapePolicy::Doubled => None
+        }
+    }
+}
+
+/// Escape sequence that could not be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidEscape {
+    /// Byte offset of the escape sequence in the decoded text
+    pub offset: usize,
+    /// The escape sequence as it appears in the text
+    pub sequence: String
+}
+
+/// Table of escape sequences that are decoded in a region
+///
+/// Each escape sequence consists of the escape symbol followed by a letter.
+/// The escape symbol followed by itself always results in the escape symbol.
+///
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let escapes = EscapeTable::standard()
+///     .add_mapping('e', "\x1b");
+/// reg!(string as "string literal" => {
+///     begin: "'",
+///     end: "'",
+///     escapes: Some(escapes)
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EscapeTable {
+    /// Letters that follow the escape symbol and the text they are decoded to
+    pub mappings: HashMap<char, String>,
+    /// Decode unicode escapes such as `\u{1F600}`
+    pub unicode: bool,
+    /// Decode hexadecimal escapes such as `\x41` (only ASCII characters up to `\x7F`)
+    pub hex: bool
+}
+
+impl EscapeTable {
+    /// Create an empty escape table
+    pub fn new() -> Self {
+        EscapeTable::default()
+    }
+
+    /// Create an escape table with the most common escape sequences
+    ///
+    /// These are `\n`, `\t`, `\r`, `\0`, `\'`, `\"`, `\u{...}` and `\x..`
+    pub fn standard() -> Self {
+        EscapeTable::new()
+            .add_mapping('n', "\n")
+            .add_mapping('t', "\t")
+            .add_mapping('r', "\r")
+            .add_mapping('0', "\0")
+            .add_mapping('\'', "'")
+            .add_mapping('"', "\"")
+            .allow_unicode()
+            .allow_hex()
+    }
+
+    /// Add a custom escape sequence
+    pub fn add_mapping<T: AsRef<str>>(mut self, letter: char, value: T) -> Self {
+        self.mappings.insert(letter, String::from(value.as_ref()));
+        self
+    }
+
+    /// Decode unicode escapes such as `\u{1F600}`
+    pub fn allow_unicode(mut self) -> Self {
+        self.unicode = true;
+        self
+    }
+
+    /// Decode hexadecimal escapes such as `\x41`
+    ///
+    /// Values above `\x7F` are not valid (just like in Rust) since they are not ASCII characters.
+    pub fn allow_hex(mut self) -> Self {
+        self.hex = true;
+        self
+    }
+
+    // Decodes the code point of unicode or hexadecimal escape
+    // and returns it with the length of the sequence (without the escape symbol) in bytes
+    fn decode_code_point(&self, letter: char, rest: &str) -> Option<(char, usize)> {
+        match letter {
+            'u' if self.unicode => {
+                let digits = rest.strip_prefix("u{")?;
+                let size = digits.find('}')?;
+                if size == 0 || size > 6 {
+                    return None
+                }
+                let code = u32::from_str_radix(&digits[..size], 16).ok()?;
+                Some((char::from_u32(code)?, size + 3))
+            }
+            'x' if self.hex => {
+                let digits = rest.get(1..3)?;
+                let code = u8::from_str_radix(digits, 16).ok().filter(u8::is_ascii)?;
+                Some((char::from(code), 3))
+            }
+            _ => None
+        }
+    }
+
+    /// Decode escape sequences in the text
+    ///
+    /// Returns the decoded text and escape sequences that are not valid.
+    /// Invalid escape sequences are left in the decoded text as they are.
+    pub fn decode(&self, text: &str, escape: char) -> (String, Vec<InvalidEscape>) {
+        let mut result = String::with_capacity(text.len());
+        let mut errors = vec![];
+        let mut letters = text.char_indices();
+        while let Some((offset, letter)) = letters.next() {
+            if letter != escape {
+                result.push(letter);
+                continue
+            }
+            let rest = &text[offset + letter.len_utf8()..];
+            let Some(next) = rest.chars().next() else {
+                errors.push(InvalidEscape { offset, sequence: letter.to_string() });
+                result.push(letter);
+                break
+            };
+            let size = if next == escape {
+                result.push(escape);
+                next.len_utf8()
+            }
+            else if let Some(value) = self.mappings.get(&next) {
+                result.push_str(value);
+                next.len_utf8()
+            }
+            else if let Some((code_point, size)) = self.decode_code_point(next, rest) {
+                result.push(code_point);
+                size
+            }
+            else {
+                // Keep the invalid sequence as it is
+                errors.push(InvalidEscape { offset, sequence: format!("{letter}{next}") });
+                result.push(letter);
+                result.push(next);
+                next.len_utf8()
+            };
+            // Skip the letters of the sequence
+            let end = offset + letter.len_utf8() + size;
+            while letters.offset() < end && letters.next().is_some() {}
+        }
+        (result, errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ EscapeTable, InvalidEscape };
+
+    #[test]
+    fn decode_escapes() {
+        let escapes = EscapeTable::standard().add_mapping('$', "$");
+        let (result, errors) = escapes.decode("'a\\n\\t\\\\ \\u{17C} \\x41 \\$x\\''", '\\');
+        assert_eq!(result, "'a\n\t\\ ż A $x''");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn invalid_escapes() {
+        let escapes = EscapeTable::new().add_mapping('n', "\n");
+        let (result, errors) = escapes.decode("a\\qb\\u{41}\\", '\\');
+        assert_eq!(result, "a\\qb\\u{41}\\");
+        assert_eq!(errors, vec![
+            InvalidEscape { offset: 1, sequence: "\\q".to_string() },
+            InvalidEscape { offset: 4, sequence: "\\u".to_string() },
+            InvalidEscape { offset: 10, sequence: "\\".to_string() }
+        ]);
+    }
+
+    #[test]
+    fn non_ascii_hex_escapes() {
+        let escapes = EscapeTable::standard();
+        let (result, errors) = escapes.decode("\\x7F\\x80\\xFF", '\\');
+        assert_eq!(result, "\x7F\\x80\\xFF");
+        assert_eq!(errors, vec![
+            InvalidEscape { offset: 4, sequence: "\\x".to_string() },
+            InvalidEscape { offset: 8, sequence: "\\x".to_string() }
+        ]);
+    }
+}
@@ -0,0 +1,65 @@
+use std::fmt::{ Debug, Formatter };
+use std::sync::Arc;
+use crate::compiling::Token;
+
+/// Function that receives the token that precedes the region
+/// (none at the beginning of the code) and decides if the region can begin
+pub type GuardFn = dyn Fn(Option<&Token>) -> bool + Send + Sync;
+
+/// Predicate that decides if a region can begin where its `begin` appears
+///
+/// Some regions depend on the context in which they appear. For instance in JavaScript
+/// `/` begins a regular expression after an operator, but it's a division after an operand.
+/// Guard receives the previous token (including the word that is being lexed right now)
+/// and the region begins only if the guard allows it.
+///
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let guard = RegionGuard::new("after operator", |previous| match previous {
+///     Some(token) => match token.kind {
+///         TokenKind::Word => false,
+///         TokenKind::Keyword => token.word == "return",
+///         _ => ![")", "]"].contains(&token.word.as_str())
+///     },
+///     None => true
+/// });
+/// reg!(regex as "regular expression" => {
+///     begin: "/",
+///     end: "/",
+///     guard: Some(guard)
+/// });
+/// ```
+#[derive(Clone)]
+pub struct RegionGuard {
+    /// Name of this guard
+    pub name: String,
+    guard: Arc<GuardFn>
+}
+
+impl RegionGuard {
+    /// Create a new guard from a function
+    pub fn new<T: AsRef<str>>(name: T, guard: impl Fn(Option<&Token>) -> bool + Send + Sync + 'static) -> Self {
+        RegionGuard {
+            name: String::from(name.as_ref()),
+            guard: Arc::new(guard)
+        }
+    }
+
+    /// Check if the region can begin after given token
+    pub fn allows(&self, previous: Option<&Token>) -> bool {
+        (self.guard)(previous)
+    }
+}
+
+impl Debug for RegionGuard {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "RegionGuard({})", self.name)
+    }
+}
+
+impl PartialEq for RegionGuard {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && Arc::ptr_eq(&self.guard, &other.guard)
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::BTreeSet;
+use std::fmt::{ Debug, Formatter };
+use std::sync::Arc;
+
+/// Function that receives the code that is ahead of the lexer
+/// and returns the length (in bytes) of the token that it matched
+pub type MatcherFn = dyn Fn(&str) -> Option<usize> + Send + Sync;
+
+/// Custom rule that lexes a token on its own
+///
+/// Token matchers are tried in order at the beginning of every token in a tokenized region.
+/// The first one that matches takes priority over symbol splitting and compounds.
+/// This is useful for tokens such as numeric literals `1e-5`, identifiers with dashes `foo-bar`
+/// or sigils `$var` that would be otherwise split by symbols.
+///
+/// # Example
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let number = TokenMatcher::pattern("number", "[0-9][0-9_]*(\\.[0-9]+)?(e-?[0-9]+)?");
+/// let sigil = TokenMatcher::new("sigil", |code| {
+///     let name = code.strip_prefix('$')?;
+///     let size = name.chars().take_while(|letter| letter.is_alphanumeric()).count();
+///     (size > 0).then(|| size + 1)
+/// });
+/// Rules::new(vec!['.', '-', '$'], vec![], reg![])
+///     .add_matcher(number)
+///     .add_matcher(sigil);
+/// ```
+#[derive(Clone)]
+pub struct TokenMatcher {
+    /// Name of this matcher
+    pub name: String,
+    matcher: Arc<MatcherFn>
+}
+
+impl TokenMatcher {
+    /// Create a new matcher from a function
+    ///
+    /// The function receives the rest of the code and
+    /// returns the length of the matched token in bytes.
+    pub fn new<T: AsRef<str>>(name: T, matcher: impl Fn(&str) -> Option<usize> + Send + Sync + 'static) -> Self {
+        TokenMatcher {
+            name: String::from(name.as_ref()),
+            matcher: Arc::new(matcher)
+        }
+    }
+
+    /// Create a new matcher from a pattern
+    ///
+    /// Pattern is a small subset of regular expressions. It supports:
+    ///  - literal characters (special ones can be escaped with `\`)
+    ///  - any character `.` (use `\.` to match a dot)
+    ///  - character classes such as `[a-z_]` or negated `[^0-9]`
+    ///  - groups `( ... )`
+    ///  - quantifiers `?`, `*` and `+`
+    ///
+    /// Pattern always matches the longest possible token.
+    ///
+    /// # Panics
+    /// This function panics if the pattern is not valid.
+    pub fn pattern<T: AsRef<str>>(name: T, pattern: T) -> Self {
+        let items = match Pattern::parse(pattern.as_ref()) {
+            Ok(items) => items,
+            Err(message) => panic!("Invalid pattern of matcher '{}': {message}", name.as_ref())
+        };
+        TokenMatcher::new(name, move |code| {
+            Pattern::match_items(&items, code, BTreeSet::from([0]))
+                .last()
+                .copied()
+                .filter(|size| *size > 0)
+        })
+    }
+
+    /// Match the beginning of the code and return length of the token in bytes
+    pub fn matches(&self, code: &str) -> Option<usize> {
+        (self.matcher)(code).filter(|size| *size > 0 && code.is_char_boundary(*size))
+    }
+}
+
+impl Debug for TokenMatcher {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "TokenMatcher({})", self.name)
+    }
+}
+
+impl PartialEq for TokenMatcher {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && Arc::ptr_eq(&self.matcher, &other.matcher)
+    }
+}
+
+// Single element of the pattern
+enum Node {
+    Letter(char),
+    Any,
+    Class(Vec<(char, char)>, bool),
+    Group(Vec<Item>)
+}
+
+// Element of the pattern with its quantifier
+struct Item {
+    node: Node,
+    min: usize,
+    max: Option<usize>
+}
+
+struct Pattern;
+
+impl Pattern {
+    fn parse(pattern: &str) -> Result<Vec<Item>, String> {
+        let mut letters = pattern.chars().peekable();
+        let items = Self::parse_sequence(&mut letters)?;
+        match letters.next() {
+            Some(letter) => Err(format!("unexpected '{letter}'")),
+            None => Ok(items)
+        }
+    }
+
+    fn parse_sequence(letters: &mut std::iter::Peekable<std::str::Chars>) -> Result<Vec<Item>, String> {
+        let mut items = vec![];
+        while let Some(letter) = letters.peek().copied() {
+            let node = match letter {
+                ')' => break,
+                '(' => {
+                    letters.next();
+                    let group = Self::parse_sequence(letters)?;
+                    if letters.next() != Some(')') {
+                        return Err(String::from("unclosed group"))
+                    }
+                    Node::Group(group)
+                }
+                '[' => {
+                    letters.next();
+                    Self::parse_class(letters)?
+                }
+                '.' => {
+                    letters.next();
+                    Node::Any
+                }
+                '?' | '*' | '+' => return Err(format!("nothing to repeat with '{letter}'")),
+                '\\' => {
+                    letters.next();
+                    Node::Letter(letters.next().ok_or("dangling escape")?)
+                }
+                _ => {
+                    letters.next();
+                    Node::Letter(letter)
+                }
+            };
+            let (min, max) = match letters.peek() {
+                Some('?') => (0, Some(1)),
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                _ => (1, Some(1))
+            };
+            if max != Some(1) || min != 1 {
+                letters.next();
+            }
+            items.push(Item { node, min, max });
+        }
+        Ok(items)
+    }
+
+    fn parse_class(letters: &mut std::iter::Peekable<std::str::Chars>) -> Result<Node, String> {
+        let mut ranges = vec![];
+        let is_negated = letters.next_if_eq(&'^').is_some();
+        loop {
+            let begin = match letters.next() {
+                Some(']') if !ranges.is_empty() => break,
+                Some('\\') => letters.next().ok_or("dangling escape")?,
+                Some(letter) => letter,
+                None => return Err(String::from("unclosed character class"))
+            };
+            let end = if letters.next_if_eq(&'-').is_some() {
+                match letters.next() {
+                    Some(']') => {
+                        ranges.push((begin, begin));
+                        ranges.push(('-', '-'));
+                        break
+                    }
+                    Some('\\') => letters.next().ok_or("dangling escape")?,
+                    Some(letter) => letter,
+                    None => return Err(String::from("unclosed character class"))
+                }
+            } else { begin };
+            ranges.push((begin, end));
+        }
+        Ok(Node::Class(ranges, is_negated))
+    }
+
+    // Returns all byte offsets at which given sequence can end
+    fn match_items(items: &[Item], code: &str, mut positions: BTreeSet<usize>) -> BTreeSet<usize> {
+        for item in items.iter() {
+            positions = Self::match_item(item, code, positions);
+            if positions.is_empty() {
+                break
+            }
+        }
+        positions
+    }
+
+    fn match_item(item: &Item, code: &str, positions: BTreeSet<usize>) -> BTreeSet<usize> {
+        let mut result = if item.min == 0 { positions.clone() } else { BTreeSet::new() };
+        let mut seen = positions.clone();
+        let mut current = positions;
+        let mut count = 0;
+        while item.max.is_none_or(|max| count < max) {
+            current = Self::match_node(&item.node, code, current);
+            // Stop if no new position can be reached
+            current.retain(|position| !seen.contains(position) || count < item.min);
+            if current.is_empty() {
+                break
+            }
+            count += 1;
+            seen.extend(current.iter().copied());
+            if count >= item.min {
+                result.extend(current.iter().copied());
+            }
+        }
+        result
+    }
+
+    fn match_node(node: &Node, code: &str, positions: BTreeSet<usize>) -> BTreeSet<usize> {
+        if let Node::Group(items) = node {
+            return Self::match_items(items, code, positions)
+        }
+        positions.into_iter().filter_map(|position| {
+            let letter = code[position..].chars().next()?;
+            let is_match = match node {
+                Node::Letter(expected) => letter == *expected,
+                Node::Any => letter != '\n',
+                Node::Class(ranges, is_negated) => {
+                    ranges.iter().any(|(begin, end)| (*begin..=*end).contains(&letter)) != *is_negated
+                }
+                Node::Group(_) => unreachable!()
+            };
+            is_match.then(|| position + letter.len_utf8())
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TokenMatcher;
+
+    #[test]
+    fn match_pattern() {
+        let number = TokenMatcher::pattern("number", "[0-9][0-9_]*(\\.[0-9]+)?(e-?[0-9]+)?");
+        assert_eq!(number.matches("1_000 + 1"), Some(5));
+        assert_eq!(number.matches("12.24+.123"), Some(5));
+        assert_eq!(number.matches("1e-5)"), Some(4));
+        assert_eq!(number.matches("1.e"), Some(1));
+        assert_eq!(number.matches("abc"), None);
+        let hex = TokenMatcher::pattern("hex", "0x[0-9A-F]+");
+        assert_eq!(hex.matches("0x1F;"), Some(4));
+        let dashed = TokenMatcher::pattern("identifier", "[a-z][a-z-]*");
+        assert_eq!(dashed.matches("foo-bar baz"), Some(7));
+    }
+
+    #[test]
+    fn match_function() {
+        let sigil = TokenMatcher::new("sigil", |code| {
+            let name = code.strip_prefix('$')?;
+            let size = name.chars().take_while(|letter| letter.is_alphanumeric()).count();
+            (size > 0).then(|| size + 1)
+        });
+        assert_eq!(sigil.matches("$var = 1"), Some(4));
+        assert_eq!(sigil.matches("$ var"), None);
+    }
+}
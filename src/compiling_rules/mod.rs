@@ -6,6 +6,16 @@
 #[macro_use]
 mod rules;
 mod region;
+mod matcher;
+mod escape;
+mod guard;
+mod char_class;
+mod validation;
 
 pub use rules::*;
-pub use region::*;
\ No newline at end of file
+pub use region::*;
+pub use matcher::*;
+pub use escape::*;
+pub use guard::*;
+pub use char_class::*;
+pub use validation::*;
\ No newline at end of file
@@ -1,8 +1,31 @@
 use std::collections::HashMap;
+use super::{ EscapeTable, EscapePolicy, RegionGuard };
 
 /// This is a type of a map that is generated by `generate_region_map` method of region's
 pub type RegionMap = HashMap<String,Region>;
 
+/// Determines how the region ends based on the text that has opened it
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub enum Delimiter {
+    /// Region begins with `begin` and ends with `end`
+    #[default]
+    Fixed,
+    /// Given character can repeat any number of times in the place where it appears in `begin`.
+    /// Then `end` has to contain the same number of these characters in the place where it appears there.
+    /// This way you can express raw strings such as `r#"..."#` (`begin: "r#\""`, `end: "\"#"`)
+    /// or long brackets `[==[ ... ]==]` (`begin: "[=["`, `end: "]=]"`).
+    Repeat(char),
+    /// The `begin` is followed by an identifier (that can be quoted) and the region
+    /// ends with a line that consists of this identifier. This way you can express
+    /// heredocs such as `<<EOF ... EOF` (`begin: "<<"`, `end: ""`).
+    Identifier,
+    /// The `begin` has to end its line and the region ends right before the first following line
+    /// that is not blank and is not indented more than the line with the `begin`. This way you can
+    /// express block scalars such as `key: |` followed by indented lines (`begin: "|"`, `end: ""`).
+    /// The common indentation of the region is stripped from the `value` of the token.
+    Indentation
+}
+
 /// Convenience macro that creates regions
 /// 
 /// This macro can create four types of region
@@ -29,6 +52,17 @@ pub type RegionMap = HashMap<String,Region>;
 ///  - `tokenize`
 ///  - `allow_left_open`
 ///  - `singleline`
+///  - `nestable`
+///  - `delimiter`
+///  - `strip_indent`
+///  - `escapes`
+///  - `escape`
+///  - `trivia`
+///  - `alternatives`
+///  - `alternative_ends`
+///  - `end_at_eof`
+///  - `guard`
+///  - `balanced`
 /// 
 /// ```
 /// # use heraclitus_compiler::prelude::*;
@@ -109,6 +143,103 @@ macro_rules! reg {
 ///  - `tokenize`
 ///  - `allow_left_open`
 ///  - `singleline`
+///  - `nestable`
+///  - `delimiter`
+///  - `strip_indent`
+///  - `escapes`
+///  - `escape`
+///  - `trivia`
+///  - `alternatives`
+///  - `alternative_ends`
+///  - `end_at_eof`
+///  - `guard`
+///  - `balanced`
+///
+/// Nestable region counts its own nested begins and ends,
+/// so that block comments such as `/* /* */ */` close at the right place.
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// reg!(comment as "block comment" => {
+///     begin: "/*",
+///     end: "*/",
+///     nestable: true
+/// });
+/// ```
+///
+/// Each region can have its own escape policy. For instance raw strings can disable escaping
+/// and SQL strings can escape the closing delimiter by doubling it.
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// reg![
+///     reg!(raw as "raw string" => {
+///         begin: "r'",
+///         end: "'",
+///         escape: EscapePolicy::Disabled
+///     }),
+///     reg!(sql as "sql string" => {
+///         begin: "'",
+///         end: "'",
+///         escape: EscapePolicy::Doubled
+///     })
+/// ];
+/// ```
+///
+/// Token of a trivia region (such as a comment) is flagged with `Token::trivia`, so the parser does not see it.
+/// In lossless mode its text is kept in the trivia of the surrounding tokens as well.
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// reg!(comment as "comment" => {
+///     begin: "//",
+///     end: "\n",
+///     trivia: true
+/// });
+/// ```
+///
+/// Region can begin and end with several strings. Alternative pairs are closed with the end
+/// of the pair that has opened the region, while alternative ends can close the region regardless.
+/// Region that ends at the end of the code closes there without being reported as unclosed.
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// reg![
+///     reg!(string as "string" => {
+///         begin: "'",
+///         end: "'",
+///         alternatives: vec![(String::from("\""), String::from("\""))]
+///     }),
+///     reg!(comment as "comment" => {
+///         begin: "//",
+///         end: "\n",
+///         alternative_ends: vec![String::from("\r\n")],
+///         end_at_eof: true
+///     })
+/// ];
+/// ```
+///
+/// Balanced region counts the pairs of given brackets inside of itself and ends only once all of them are closed.
+/// This way the interpolation `${ {a: 1}.a }` does not end at the first closing brace.
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// reg!(interp as "interpolation" => {
+///     begin: "${",
+///     end: "}",
+///     tokenize: true,
+///     balanced: vec![('{', '}')]
+/// } ref global);
+/// ```
+///
+/// Region with a dynamic delimiter ends with a delimiter that is derived from the one that has opened it
+/// (or with the indentation of the line where it has been opened).
+/// The `strip_indent` option removes common indentation of the lines that follow the opening line
+/// and stores the result in the `value` of the token.
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// reg!(heredoc as "heredoc" => {
+///     begin: "<<",
+///     end: "",
+///     delimiter: Delimiter::Identifier,
+///     strip_indent: true
+/// });
+/// ```
 #[derive(Debug, PartialEq, Clone)]
 pub struct Region {
     /// identifier that will be used to reference this region in an interpolation
@@ -133,7 +264,37 @@ pub struct Region {
     /// go past the new line character
     pub references: Option<String>,
     /// Region can be a reference to some other region
-    pub singleline: bool
+    pub singleline: bool,
+    /// Determines if this region can be nested
+    /// inside of itself (such as block comments)
+    pub nestable: bool,
+    /// Determines how the closing delimiter is derived from the opening one
+    pub delimiter: Delimiter,
+    /// Determines if common indentation should be stripped
+    /// from the lines that follow the opening line
+    pub strip_indent: bool,
+    /// Escape sequences that are decoded in the value of the token
+    pub escapes: Option<EscapeTable>,
+    /// Determines how the contents of this region can be escaped
+    pub escape: EscapePolicy,
+    /// Determines if this region is trivia (such as a comment)
+    /// that is hidden from the parser
+    ///
+    /// With indentation scoping a line that holds only trivia does not change the indentation level.
+    pub trivia: bool,
+    /// Additional pairs of begin and end strings of this region
+    pub alternatives: Vec<(String, String)>,
+    /// Additional strings that end this region
+    pub alternative_ends: Vec<String>,
+    /// Determines if this region closes
+    /// when the code ends
+    pub end_at_eof: bool,
+    /// Predicate that decides if this region
+    /// can begin after the previous token
+    pub guard: Option<RegionGuard>,
+    /// Pairs of brackets that have to be balanced
+    /// before this region can end
+    pub balanced: Vec<(char, char)>
 }
 
 impl Region {
@@ -149,6 +310,17 @@ impl Region {
             allow_left_open: false,
             global: false,
             singleline: false,
+            nestable: false,
+            delimiter: Delimiter::Fixed,
+            strip_indent: false,
+            escapes: None,
+            escape: EscapePolicy::Inherit,
+            trivia: false,
+            alternatives: vec![],
+            alternative_ends: vec![],
+            end_at_eof: false,
+            guard: None,
+            balanced: vec![],
             references: references.map(|value| String::from(value.as_ref()))
         }
     }
@@ -162,6 +334,55 @@ impl Region {
         reg
     }
 
+    /// Match the beginning of this region at the beginning of given code
+    ///
+    /// Returns the delimiter that opens this region in the code
+    /// and the delimiter that will close this occurrence of the region.
+    pub fn match_delimiter(&self, code: &str) -> Option<(String, String)> {
+        match &self.delimiter {
+            Delimiter::Fixed => std::iter::once((&self.begin, &self.end))
+                .chain(self.alternatives.iter().map(|(begin, end)| (begin, end)))
+                .find(|(begin, _)| code.starts_with(begin.as_str()))
+                .map(|(begin, end)| (begin.clone(), end.clone())),
+            Delimiter::Repeat(letter) => {
+                let (prefix, suffix) = self.begin.split_once(*letter).unwrap_or((&self.begin, ""));
+                let rest = code.strip_prefix(prefix)?;
+                let count = rest.chars().take_while(|item| item == letter).count();
+                if !rest[count * letter.len_utf8()..].starts_with(suffix) {
+                    return None
+                }
+                let repeated = letter.to_string().repeat(count);
+                let end = match self.end.split_once(*letter) {
+                    Some((prefix, suffix)) => format!("{prefix}{repeated}{suffix}"),
+                    None => self.end.clone()
+                };
+                Some((format!("{prefix}{repeated}{suffix}"), end))
+            }
+            Delimiter::Identifier => {
+                let rest = code.strip_prefix(self.begin.as_str())?;
+                let quote = rest.chars().next().filter(|letter| ['\'', '"'].contains(letter));
+                let rest = &rest[quote.map_or(0, char::len_utf8)..];
+                let size = rest.find(|letter: char| !letter.is_alphanumeric() && letter != '_').unwrap_or(rest.len());
+                let identifier = &rest[..size];
+                if identifier.is_empty() {
+                    return None
+                }
+                let quote = match quote {
+                    Some(quote) if rest[size..].starts_with(quote) => quote.to_string(),
+                    Some(_) => return None,
+                    None => String::new()
+                };
+                Some((format!("{}{quote}{identifier}{quote}", self.begin), identifier.to_string()))
+            }
+            // The end is determined by the indentation of the line
+            Delimiter::Indentation => {
+                let rest = code.strip_prefix(self.begin.as_str())?;
+                let line = rest.split('\n').next().unwrap_or_default();
+                line.trim().is_empty().then(|| (self.begin.clone(), String::new()))
+            }
+        }
+    }
+
     /// Generate a region for region handler
     /// 
     /// This functionality is required if we want to reference other regions.
@@ -182,7 +403,7 @@ impl Region {
 #[cfg(test)]
 mod test {
     use std::collections::HashMap;
-    use super::{ Region, RegionMap };
+    use super::{ Delimiter, EscapePolicy, Region, RegionMap };
 
     #[test]
     fn region_parses_correctly() {
@@ -207,12 +428,34 @@ mod test {
                             tokenize: true,
                             allow_left_open: false,
                             singleline: false,
+                            nestable: false,
+                            delimiter: Delimiter::Fixed,
+                            strip_indent: false,
+                            escapes: None,
+                            escape: EscapePolicy::Inherit,
+                            trivia: false,
+                            alternatives: vec![],
+                            alternative_ends: vec![],
+                            end_at_eof: false,
+                            guard: None,
+                            balanced: vec![],
                             global: false,
                             references: Some(format!("global"))
                         }],
                     tokenize: false,
                     allow_left_open: false,
                     singleline: false,
+                    nestable: false,
+                    delimiter: Delimiter::Fixed,
+                    strip_indent: false,
+                    escapes: None,
+                    escape: EscapePolicy::Inherit,
+                    trivia: false,
+                    alternatives: vec![],
+                    alternative_ends: vec![],
+                    end_at_eof: false,
+                    guard: None,
+                    balanced: vec![],
                     global: false,
                     references: None
                 }],
@@ -220,6 +463,17 @@ mod test {
             allow_left_open: true,
             global: true,
             singleline: false,
+            nestable: false,
+            delimiter: Delimiter::Fixed,
+            strip_indent: false,
+            escapes: None,
+            escape: EscapePolicy::Inherit,
+            trivia: false,
+            alternatives: vec![],
+            alternative_ends: vec![],
+            end_at_eof: false,
+            guard: None,
+            balanced: vec![],
             references: None
         };
         let result = reg![
@@ -250,6 +504,17 @@ mod test {
             allow_left_open: false,
             global: false,
             singleline: false,
+            nestable: false,
+            delimiter: Delimiter::Fixed,
+            strip_indent: false,
+            escapes: None,
+            escape: EscapePolicy::Inherit,
+            trivia: false,
+            alternatives: vec![],
+            alternative_ends: vec![],
+            end_at_eof: false,
+            guard: None,
+            balanced: vec![],
             references: Some(
                 "global".to_string(),
             ),
@@ -276,6 +541,17 @@ mod test {
                                 allow_left_open: false,
                                 global: false,
                                 singleline: false,
+                                nestable: false,
+                                delimiter: Delimiter::Fixed,
+                                strip_indent: false,
+                                escapes: None,
+                                escape: EscapePolicy::Inherit,
+                                trivia: false,
+                                alternatives: vec![],
+                                alternative_ends: vec![],
+                                end_at_eof: false,
+                                guard: None,
+                                balanced: vec![],
                                 references: Some(
                                     "global".to_string(),
                                 ),
@@ -285,6 +561,17 @@ mod test {
                         allow_left_open: false,
                         global: false,
                         singleline: false,
+                        nestable: false,
+                        delimiter: Delimiter::Fixed,
+                        strip_indent: false,
+                        escapes: None,
+                        escape: EscapePolicy::Inherit,
+                        trivia: false,
+                        alternatives: vec![],
+                        alternative_ends: vec![],
+                        end_at_eof: false,
+                        guard: None,
+                        balanced: vec![],
                         references: None,
                     },
                 ],
@@ -292,6 +579,17 @@ mod test {
                 allow_left_open: true,
                 global: true,
                 singleline: false,
+                nestable: false,
+                delimiter: Delimiter::Fixed,
+                strip_indent: false,
+                escapes: None,
+                escape: EscapePolicy::Inherit,
+                trivia: false,
+                alternatives: vec![],
+                alternative_ends: vec![],
+                end_at_eof: false,
+                guard: None,
+                balanced: vec![],
                 references: None,
         });
         expected.insert("string".to_string(), Region {
@@ -310,6 +608,17 @@ mod test {
                     allow_left_open: false,
                     global: false,
                     singleline: false,
+                    nestable: false,
+                    delimiter: Delimiter::Fixed,
+                    strip_indent: false,
+                    escapes: None,
+                    escape: EscapePolicy::Inherit,
+                    trivia: false,
+                    alternatives: vec![],
+                    alternative_ends: vec![],
+                    end_at_eof: false,
+                    guard: None,
+                    balanced: vec![],
                     references: Some(
                         "global".to_string(),
                     ),
@@ -319,6 +628,17 @@ mod test {
             allow_left_open: false,
             global: false,
             singleline: false,
+            nestable: false,
+            delimiter: Delimiter::Fixed,
+            strip_indent: false,
+            escapes: None,
+            escape: EscapePolicy::Inherit,
+            trivia: false,
+            alternatives: vec![],
+            alternative_ends: vec![],
+            end_at_eof: false,
+            guard: None,
+            balanced: vec![],
             references: None,
         });
         let region = reg![
@@ -336,4 +656,46 @@ mod test {
         let result = region.generate_region_map();
         assert_eq!(expected, result);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn region_dynamic_delimiter() {
+        let raw = reg!(raw as "Raw string" => {
+            begin: "r#\"",
+            end: "\"#",
+            delimiter: Delimiter::Repeat('#')
+        });
+        assert_eq!(raw.match_delimiter("r##\"a\"##"), Some(("r##\"".to_string(), "\"##".to_string())));
+        assert_eq!(raw.match_delimiter("r\"a\""), Some(("r\"".to_string(), "\"".to_string())));
+        assert_eq!(raw.match_delimiter("r#a"), None);
+        let long = reg!(long as "Long string" => {
+            begin: "[=[",
+            end: "]=]",
+            delimiter: Delimiter::Repeat('=')
+        });
+        assert_eq!(long.match_delimiter("[==[ a ]==]"), Some(("[==[".to_string(), "]==]".to_string())));
+        let heredoc = reg!(heredoc as "Heredoc" => {
+            begin: "<<",
+            end: "",
+            delimiter: Delimiter::Identifier
+        });
+        assert_eq!(heredoc.match_delimiter("<<EOF\n"), Some(("<<EOF".to_string(), "EOF".to_string())));
+        assert_eq!(heredoc.match_delimiter("<<'END_1' x"), Some(("<<'END_1'".to_string(), "END_1".to_string())));
+        assert_eq!(heredoc.match_delimiter("<<'EOF"), None);
+        assert_eq!(heredoc.match_delimiter("<< EOF"), None);
+        let block = reg!(block as "Block" => {
+            begin: "|",
+            end: "",
+            delimiter: Delimiter::Indentation
+        });
+        assert_eq!(block.match_delimiter("| \n  a"), Some(("|".to_string(), String::new())));
+        assert_eq!(block.match_delimiter("| a\n"), None);
+        let string = reg!(string as "String" => {
+            begin: "'",
+            end: "'",
+            alternatives: vec![("\"".to_string(), "\"".to_string()), ("<<".to_string(), ">>".to_string())]
+        });
+        assert_eq!(string.match_delimiter("\"a\""), Some(("\"".to_string(), "\"".to_string())));
+        assert_eq!(string.match_delimiter("<<a>>"), Some(("<<".to_string(), ">>".to_string())));
+        assert_eq!(string.match_delimiter("<a>"), None);
+    }
+}
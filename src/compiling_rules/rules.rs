@@ -1,4 +1,7 @@
+use std::collections::HashSet;
 use super::region::Region;
+use super::matcher::TokenMatcher;
+use super::char_class::CharClass;
 
 /// Determine lexing rules for the parser
 /// 
@@ -23,6 +26,32 @@ use super::region::Region;
 /// ];
 /// Rules::new(symbols, compounds, region);
 /// ```
+///
+/// Keywords of the language can be reserved with `set_keywords`.
+/// Tokens that match a keyword are tagged by the lexer as `TokenKind::Keyword`.
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// Rules::new(vec![], vec![], reg![])
+///     .set_keywords(vec!["if", "else", "let"]);
+/// ```
+///
+/// Pairs of compounds chain together when they overlap (`('+', '+')` merges `+++` into a single token).
+/// Compounds that are longer than two characters can be added with `add_compounds`.
+/// These are matched as a whole and the lexer always picks the longest compound that matches.
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// let symbols = vec!['=', '!', '.'];
+/// Rules::new(symbols, vec![('=', '=')], reg![])
+///     .add_compounds(vec!["===", "!==", "..."]);
+/// ```
+///
+/// Scripts can begin with a shebang line such as `#!/usr/bin/env amber`.
+/// With `allow_shebang` the lexer skips this line as trivia.
+/// ```
+/// # use heraclitus_compiler::prelude::*;
+/// Rules::new(vec![], vec![], reg![])
+///     .allow_shebang();
+/// ```
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Rules {
@@ -36,7 +65,18 @@ pub struct Rules {
     /// Escape symbol
     pub escape_symbol: char,
     /// Vector of pairs of symbols that should be merged together
-    pub compounds: Vec<(char, char)>
+    pub compounds: Vec<(char, char)>,
+    /// Vector of symbol sequences of any length that should be merged together
+    pub long_compounds: Vec<String>,
+    /// Reserved words of the language
+    pub keywords: HashSet<String>,
+    /// Custom token matchers that are tried in order before splitting by symbols
+    pub matchers: Vec<TokenMatcher>,
+    /// Determines if the first line of the code can be a shebang (`#!...`)
+    pub shebang: bool,
+    /// Classes of characters that words outside of regions can consist of
+    /// (if empty, then any character is allowed)
+    pub allowed_chars: Vec<CharClass>
 }
 
 impl Rules {
@@ -45,8 +85,13 @@ impl Rules {
         Rules {
             symbols,
             compounds,
+            long_compounds: vec![],
             region_tree,
-            escape_symbol: '\\'
+            escape_symbol: '\\',
+            keywords: HashSet::new(),
+            matchers: vec![],
+            shebang: false,
+            allowed_chars: vec![]
         }
     }
 
@@ -55,4 +100,38 @@ impl Rules {
         self.escape_symbol = symbol;
         self
     }
-}
\ No newline at end of file
+
+    /// Set reserved words of your language
+    pub fn set_keywords<T: AsRef<str>>(mut self, keywords: Vec<T>) -> Self {
+        self.keywords = keywords.iter().map(|keyword| String::from(keyword.as_ref())).collect();
+        self
+    }
+
+    /// Add custom token matcher that takes priority over symbols
+    pub fn add_matcher(mut self, matcher: TokenMatcher) -> Self {
+        self.matchers.push(matcher);
+        self
+    }
+
+    /// Add compounds of any length such as `===` or `>>=`
+    ///
+    /// Unlike the pairs these compounds do not chain with the symbols that follow them.
+    pub fn add_compounds<T: AsRef<str>>(mut self, compounds: Vec<T>) -> Self {
+        self.long_compounds.extend(compounds.iter().map(|compound| String::from(compound.as_ref())));
+        self
+    }
+
+    /// Skip the shebang line (`#!...`) if the code begins with it
+    pub fn allow_shebang(mut self) -> Self {
+        self.shebang = true;
+        self
+    }
+
+    /// Set classes of characters that are allowed outside of regions
+    ///
+    /// Symbols and whitespace are always allowed. Lexer reports any other character as invalid.
+    pub fn set_allowed_chars(mut self, allowed_chars: Vec<CharClass>) -> Self {
+        self.allowed_chars = allowed_chars;
+        self
+    }
+}
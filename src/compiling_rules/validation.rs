@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fmt::{ Display, Formatter, Result };
+use super::{ Delimiter, Region, Rules };
+
+/// Problem in the rules that would make the lexer fail or behave unexpectedly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RulesError {
+    /// Region references a region id that does not exist
+    DanglingReference {
+        /// Id of the region that contains the reference
+        region: String,
+        /// Id that is referenced
+        reference: String
+    },
+    /// Different regions share the same id
+    DuplicateId(String),
+    /// Different regions share the same name
+    DuplicateName(String),
+    /// Region (other than the global one) can begin with an empty string
+    EmptyBegin(String),
+    /// Region (other than the global one) can end with an empty string
+    EmptyEnd(String),
+    /// Region can never begin, since a preceding unguarded region of the same
+    /// interpolation list begins with a prefix of its begin string
+    ShadowedBegin {
+        /// Id of the region that can never begin
+        region: String,
+        /// Id of the region that begins instead
+        shadowed_by: String
+    },
+    /// Compound contains a letter that is not a symbol, so it would split words apart
+    CompoundConflict {
+        /// Compound that contains the letter
+        compound: String,
+        /// Letter that is not a symbol
+        letter: char
+    }
+}
+
+impl Display for RulesError {
+    fn fmt(&self, formatter: &mut Formatter) -> Result {
+        match self {
+            RulesError::DanglingReference { region, reference } => {
+                write!(formatter, "Region '{region}' references region '{reference}' that does not exist")
+            }
+            RulesError::DuplicateId(id) => write!(formatter, "Region id '{id}' is used by different regions"),
+            RulesError::DuplicateName(name) => write!(formatter, "Region name '{name}' is used by different regions"),
+            RulesError::EmptyBegin(id) => write!(formatter, "Region '{id}' has an empty begin"),
+            RulesError::EmptyEnd(id) => write!(formatter, "Region '{id}' has an empty end"),
+            RulesError::ShadowedBegin { region, shadowed_by } => {
+                write!(formatter, "Region '{region}' can never begin since region '{shadowed_by}' begins first")
+            }
+            RulesError::CompoundConflict { compound, letter } => {
+                write!(formatter, "Compound '{compound}' contains '{letter}' that is not a symbol")
+            }
+        }
+    }
+}
+
+// Collects all the regions of the tree in the order of appearance
+fn collect_regions<'a>(region: &'a Region, regions: &mut Vec<&'a Region>) {
+    regions.push(region);
+    for child in region.interp.iter() {
+        collect_regions(child, regions);
+    }
+}
+
+// Strings that can begin the region
+fn get_begins(region: &Region) -> impl Iterator<Item = &String> {
+    std::iter::once(&region.begin).chain(region.alternatives.iter().map(|(begin, _)| begin))
+}
+
+// Strings that can end the region
+fn get_ends(region: &Region) -> impl Iterator<Item = &String> {
+    std::iter::once(&region.end)
+        .chain(region.alternatives.iter().map(|(_, end)| end))
+        .chain(region.alternative_ends.iter())
+}
+
+// Finds regions of the interpolation list that can never begin
+fn find_shadowed(interp: &[Region], errors: &mut Vec<RulesError>) {
+    for (index, region) in interp.iter().enumerate() {
+        let shadowing = interp[..index].iter().find(|candidate| {
+            candidate.delimiter == Delimiter::Fixed && candidate.guard.is_none() && get_begins(region).any(|begin| {
+                get_begins(candidate).any(|prefix| !prefix.is_empty() && begin.starts_with(prefix.as_str()))
+            })
+        });
+        if let Some(shadowing) = shadowing {
+            errors.push(RulesError::ShadowedBegin {
+                region: region.id.clone(),
+                shadowed_by: shadowing.id.clone()
+            });
+        }
+    }
+}
+
+impl Rules {
+    /// Check the rules and the region tree for problems
+    ///
+    /// The same region can appear in the tree multiple times as long as it's defined the same way.
+    /// Returns all the problems that have been found.
+    ///
+    /// # Example
+    /// ```
+    /// # use heraclitus_compiler::prelude::*;
+    /// let rules = Rules::new(vec![], vec![], reg![
+    ///     reg!(interp as "interpolation" => {
+    ///         begin: "{",
+    ///         end: "}"
+    ///     } ref missing)
+    /// ]);
+    /// assert_eq!(rules.validate(), Err(vec![RulesError::DanglingReference {
+    ///     region: String::from("interp"),
+    ///     reference: String::from("missing")
+    /// }]));
+    /// ```
+    pub fn validate(&self) -> std::result::Result<(), Vec<RulesError>> {
+        let mut errors = vec![];
+        let mut regions = vec![];
+        collect_regions(&self.region_tree, &mut regions);
+        let mut ids: HashMap<&str, &Region> = HashMap::new();
+        let mut names: HashMap<&str, &str> = HashMap::new();
+        for region in regions.iter() {
+            match ids.get(region.id.as_str()) {
+                Some(other) if *other != *region => errors.push(RulesError::DuplicateId(region.id.clone())),
+                Some(_) => continue,
+                None => { ids.insert(&region.id, region); }
+            }
+            match names.get(region.name.as_str()) {
+                Some(id) if *id != region.id => errors.push(RulesError::DuplicateName(region.name.clone())),
+                _ => { names.insert(&region.name, &region.id); }
+            }
+            if region.global {
+                find_shadowed(&region.interp, &mut errors);
+                continue
+            }
+            if get_begins(region).any(String::is_empty) {
+                errors.push(RulesError::EmptyBegin(region.id.clone()));
+            }
+            // Heredocs end with the identifier that follows the begin and blocks end with indentation
+            let is_dynamic = [Delimiter::Identifier, Delimiter::Indentation].contains(&region.delimiter);
+            if get_ends(region).any(String::is_empty) && !is_dynamic {
+                errors.push(RulesError::EmptyEnd(region.id.clone()));
+            }
+            // Referencing region takes the interpolations of the referenced one
+            if region.references.is_none() {
+                find_shadowed(&region.interp, &mut errors);
+            }
+        }
+        for region in regions.iter() {
+            if let Some(reference) = &region.references {
+                if !ids.contains_key(reference.as_str()) {
+                    errors.push(RulesError::DanglingReference {
+                        region: region.id.clone(),
+                        reference: reference.clone()
+                    });
+                }
+            }
+        }
+        let pairs = self.compounds.iter().map(|(left, right)| String::from_iter([left, right]));
+        for compound in pairs.chain(self.long_compounds.iter().cloned()) {
+            if let Some(letter) = compound.chars().find(|letter| !self.symbols.contains(letter)) {
+                errors.push(RulesError::CompoundConflict {
+                    compound: compound.clone(),
+                    letter
+                });
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::reg;
+    use crate::compiling::Compiler;
+    use super::{ Region, Rules, RulesError };
+
+    #[test]
+    fn valid_rules() {
+        let interp = reg!(interp as "interpolation" => {
+            begin: "{",
+            end: "}",
+            tokenize: true
+        } ref global);
+        let rules = Rules::new(vec!['=', '<'], vec![('<', '=')], reg![
+            reg!(string as "string" => {
+                begin: "'",
+                end: "'"
+            } => [interp.clone()]),
+            reg!(template as "template" => {
+                begin: "`",
+                end: "`"
+            } => [interp])
+        ]);
+        assert_eq!(rules.validate(), Ok(()));
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let rules = Rules::new(vec!['='], vec![('=', '>')], reg![
+            reg!(string as "string" => {
+                begin: "'",
+                end: ""
+            } => [
+                reg!(interp as "interpolation" => {
+                    begin: "{",
+                    end: "}"
+                } ref strings)
+            ]),
+            reg!(docstring as "docstring" => {
+                begin: "'''",
+                end: "'''"
+            }),
+            reg!(string as "text" => {
+                begin: "",
+                end: "\""
+            }),
+            reg!(text as "docstring" => {
+                begin: "\"",
+                end: "\""
+            })
+        ]);
+        let expected = vec![
+            RulesError::ShadowedBegin { region: String::from("docstring"), shadowed_by: String::from("string") },
+            RulesError::EmptyEnd(String::from("string")),
+            RulesError::DuplicateId(String::from("string")),
+            RulesError::EmptyBegin(String::from("string")),
+            RulesError::DuplicateName(String::from("docstring")),
+            RulesError::DanglingReference { region: String::from("interp"), reference: String::from("strings") },
+            RulesError::CompoundConflict { compound: String::from("=>"), letter: '>' }
+        ];
+        assert_eq!(rules.validate(), Err(expected));
+    }
+
+    #[test]
+    fn compiler_validates_rules() {
+        let valid = Rules::new(vec![], vec![], reg![
+            reg!(string as "string" => {
+                begin: "'",
+                end: "'"
+            })
+        ]);
+        assert!(Compiler::try_new("Test", valid).is_ok());
+        let invalid = Rules::new(vec![], vec![], reg![
+            reg!(interp as "interpolation" => {
+                begin: "{",
+                end: "}"
+            } ref missing)
+        ]);
+        let errors = Compiler::try_new("Test", invalid).err();
+        assert_eq!(errors, Some(vec![RulesError::DanglingReference {
+            region: String::from("interp"),
+            reference: String::from("missing")
+        }]));
+    }
+}
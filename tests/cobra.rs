@@ -21,5 +21,9 @@ fn cobra() {
     ].join("\n"));
     let mut ast = cobra_modules::IfStatement::new();
     compiler.debug();
-    assert!(compiler.compile(&mut ast).is_ok());
+    let meta = compiler.compile(&mut ast);
+    assert!(meta.is_ok());
+    // Whole block should be consumed
+    let meta = meta.unwrap();
+    assert_eq!(meta.index, meta.expr.len());
 }
@@ -15,10 +15,7 @@ impl SyntaxModule<DefaultMetadata> for IfStatement {
         token(meta, "if")?;
         syntax(meta, &mut self.cond)?;
         token(meta, ":")?;
-        let size = indent(meta)?;
-        println!("size: {size}");
-        self.block.set_indent_size(size);
-        meta.index -= 1;
+        indent(meta)?;
         syntax(meta, &mut self.block)?;
         Ok(())
     }